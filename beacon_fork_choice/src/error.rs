@@ -0,0 +1,47 @@
+use thiserror::Error;
+use types::{
+    config::Config,
+    primitives::{Epoch, Slot, H256},
+    types::{Attestation, Checkpoint, SignedBeaconBlock},
+};
+
+/// Errors returned by [`Store`] methods, wrapped in [`anyhow::Error`].
+///
+/// Use [`anyhow::Error::downcast_ref`] to match on them. Errors from the state transition are
+/// passed through unchanged and can be matched the same way.
+///
+/// [`Store`]: crate::Store
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Error)]
+pub enum Error<C: Config> {
+    #[error("slot {new_slot} is not later than {old_slot}")]
+    SlotNotLater { old_slot: Slot, new_slot: Slot },
+    #[error("block is not a descendant of finalized block (block: {block:?}, finalized_block: {finalized_block:?})")]
+    NotDescendantOfFinalized {
+        block: SignedBeaconBlock<C>,
+        finalized_block: SignedBeaconBlock<C>,
+    },
+    #[error("attestation has an invalid signature: {attestation:?}")]
+    InvalidAttestationSignature { attestation: Attestation<C> },
+    #[error("epoch {epoch} does not have a start slot representable as a u64")]
+    EpochTooLarge { epoch: Epoch },
+    #[error("block slot {block_slot} is more than {max_future_slots} slots after {slot}")]
+    BlockTooFarInFuture {
+        slot: Slot,
+        block_slot: Slot,
+        max_future_slots: Slot,
+    },
+    #[error("state for checkpoint {checkpoint:?} is not stored")]
+    MissingCheckpointState { checkpoint: Checkpoint },
+}
+
+/// A block that is missing from the [`Store`] even though a stored block descends from it.
+///
+/// Blocks waiting for it are delayed until it arrives.
+///
+/// [`Store`]: crate::Store
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Error)]
+#[error("block {root:?} is missing from the store")]
+pub struct MissingBlock {
+    pub root: H256,
+}
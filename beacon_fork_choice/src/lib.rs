@@ -5,34 +5,37 @@
 //! offending object or return `Err`. All other operations that can raise exceptions in Python
 //! (like indexing into `dict`s) are represented by statements that panic on failure.
 
-use core::{cmp::Ordering, convert::TryInto as _, mem};
-use std::collections::{BTreeMap, HashMap};
+use core::{cell::Cell, cmp::Ordering, convert::TryInto as _, mem};
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
 
-use anyhow::{ensure, Result};
+use anyhow::{bail, ensure, Result};
 use error_utils::DebugAsError;
 use helper_functions::{beacon_state_accessors, crypto, misc, predicates};
 use log::info;
 use maplit::hashmap;
-use thiserror::Error;
-use transition_functions::process_slot;
+use serde::{Deserialize, Serialize};
+use ssz::{Decode, DecodeError, Encode};
+use ssz_derive::{Decode, Encode};
+use transition_functions::{attestations::attestations::AttestableBlock as _, process_slot};
+use typenum::Unsigned as _;
 use types::{
     config::Config,
+    helper_functions_types::Error as HelperError,
     primitives::{Epoch, Gwei, Slot, ValidatorIndex, H256},
-    types::{Attestation, BeaconBlock, Checkpoint},
+    types::{
+        Attestation, BeaconBlock, BeaconBlockHeader, Checkpoint, IndexedAttestation,
+        SignedBeaconBlock,
+    },
     BeaconState,
 };
 
-#[allow(clippy::large_enum_variant)]
-#[derive(Debug, Error)]
-enum Error<C: Config> {
-    #[error("slot {new_slot} is not later than {old_slot}")]
-    SlotNotLater { old_slot: Slot, new_slot: Slot },
-    #[error("block is not a descendant of finalized block (block: {block:?}, finalized_block: {finalized_block:?})")]
-    NotDescendantOfFinalized {
-        block: BeaconBlock<C>,
-        finalized_block: BeaconBlock<C>,
-    },
-}
+pub use crate::error::{Error, MissingBlock};
+
+mod error;
+
+/// The default number of slots a block may be ahead of the `Store` before it is rejected rather
+/// than delayed.
+pub const DEFAULT_MAX_FUTURE_SLOTS: Slot = 64;
 
 /// <https://github.com/ethereum/eth2.0-specs/blob/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#latestmessage>
 type LatestMessage = Checkpoint;
@@ -40,8 +43,211 @@ type LatestMessage = Checkpoint;
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 enum DelayedObject<C: Config> {
-    BeaconBlock(BeaconBlock<C>),
+    BeaconBlock(SignedBeaconBlock<C>),
+    Attestation(Attestation<C>),
+    UnverifiedAttestation(Attestation<C>),
+}
+
+/// An attestation accepted by [`Store::on_unverified_attestation`] whose signature has not been
+/// checked yet.
+struct UnverifiedAttestation<C: Config> {
+    attestation: Attestation<C>,
+    indexed_attestation: IndexedAttestation<C>,
+}
+
+/// An input passed to one of the public `Store` methods.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum StoreEvent<C: Config> {
+    Slot(Slot),
+    BeaconBlock(SignedBeaconBlock<C>),
     Attestation(Attestation<C>),
+    UnverifiedAttestation(Attestation<C>),
+    VerifyPendingAttestations,
+}
+
+// SSZ in this version of the specification has no unions, so events are encoded the way unions
+// are in later versions: a selector byte followed by the encoding of the variant.
+impl<C: Config> Encode for StoreEvent<C> {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        let variant_len = match self {
+            StoreEvent::Slot(slot) => slot.ssz_bytes_len(),
+            StoreEvent::BeaconBlock(signed_block) => signed_block.ssz_bytes_len(),
+            StoreEvent::Attestation(attestation)
+            | StoreEvent::UnverifiedAttestation(attestation) => attestation.ssz_bytes_len(),
+            StoreEvent::VerifyPendingAttestations => 0,
+        };
+        1 + variant_len
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        match self {
+            StoreEvent::Slot(slot) => {
+                buf.push(0);
+                slot.ssz_append(buf);
+            }
+            StoreEvent::BeaconBlock(signed_block) => {
+                buf.push(1);
+                signed_block.ssz_append(buf);
+            }
+            StoreEvent::Attestation(attestation) => {
+                buf.push(2);
+                attestation.ssz_append(buf);
+            }
+            StoreEvent::UnverifiedAttestation(attestation) => {
+                buf.push(3);
+                attestation.ssz_append(buf);
+            }
+            StoreEvent::VerifyPendingAttestations => buf.push(4),
+        }
+    }
+}
+
+impl<C: Config> Decode for StoreEvent<C> {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (selector, variant_bytes) =
+            bytes.split_first().ok_or(DecodeError::InvalidByteLength {
+                len: 0,
+                expected: 1,
+            })?;
+        match selector {
+            0 => Slot::from_ssz_bytes(variant_bytes).map(StoreEvent::Slot),
+            1 => SignedBeaconBlock::from_ssz_bytes(variant_bytes).map(StoreEvent::BeaconBlock),
+            2 => Attestation::from_ssz_bytes(variant_bytes).map(StoreEvent::Attestation),
+            3 => Attestation::from_ssz_bytes(variant_bytes).map(StoreEvent::UnverifiedAttestation),
+            4 if variant_bytes.is_empty() => Ok(StoreEvent::VerifyPendingAttestations),
+            _ => Err(DecodeError::BytesInvalid(format!(
+                "invalid StoreEvent selector {} followed by {} bytes",
+                selector,
+                variant_bytes.len(),
+            ))),
+        }
+    }
+}
+
+/// A record of every input passed to a `Store` since it was created.
+///
+/// Inputs are recorded before they are processed, so the log includes rejected objects.
+/// Replaying the events on top of `anchor` with [`Store::replay`] reproduces the `Store`.
+#[derive(Clone, Debug, Deserialize, Serialize, Encode, Decode)]
+pub struct StoreEventLog<C: Config> {
+    pub anchor: BeaconState<C>,
+    pub events: Vec<StoreEvent<C>>,
+}
+
+/// A change of head to a block that does not descend from the previous head.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ReorgEvent {
+    pub old_head: H256,
+    pub new_head: H256,
+    /// The slot of the latest block that both heads descend from.
+    pub common_ancestor_slot: Slot,
+}
+
+/// Two attestations by the same validator with the same target epoch that vote for different
+/// blocks.
+///
+/// Only the first message counts toward fork choice. The conflicts are kept so that they can be
+/// turned into attester slashings.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ConflictingMessage {
+    pub validator_index: ValidatorIndex,
+    pub latest_message: Checkpoint,
+    pub conflicting_message: Checkpoint,
+}
+
+/// A summary of the fork choice state of a [`Store`] meant to be attached to bug reports.
+///
+/// States are omitted to keep it small.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ForkChoiceDump {
+    pub slot: Slot,
+    pub justified_checkpoint: Checkpoint,
+    pub finalized_checkpoint: Checkpoint,
+    pub head: H256,
+    /// Pairs of block roots and their parent roots, in order of increasing slot.
+    pub edges: Vec<(H256, H256)>,
+    /// The weights of the blocks considered by [`Store::head`].
+    pub weights: BTreeMap<H256, Gwei>,
+    /// The number of validators whose latest message votes for each block.
+    pub latest_message_counts: BTreeMap<H256, usize>,
+}
+
+/// A block considered by [`Store::head`], as returned by [`Store::viable_tree_snapshot`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct BlockNode {
+    pub root: H256,
+    pub parent_root: H256,
+    pub slot: Slot,
+    pub weight: Gwei,
+}
+
+/// The parts of a [`Store`] needed to resume it after a restart, in a form that can be encoded
+/// with SSZ.
+///
+/// Delayed objects and unverified attestations are not included because they are transient.
+#[derive(Encode, Decode)]
+pub struct PersistedStore<C: Config> {
+    slot: Slot,
+    justified_checkpoint: Checkpoint,
+    finalized_checkpoint: Checkpoint,
+    blocks: Vec<PersistedBlock<C>>,
+    checkpoint_states: CheckpointStates<C>,
+    latest_messages: Vec<PersistedLatestMessage>,
+}
+
+#[derive(Encode, Decode)]
+struct PersistedBlock<C: Config> {
+    block: SignedBeaconBlock<C>,
+    state: BeaconState<C>,
+}
+
+#[derive(Encode, Decode)]
+struct PersistedLatestMessage {
+    validator_index: ValidatorIndex,
+    message: LatestMessage,
+}
+
+/// Checkpoint states sorted by checkpoint so that their SSZ encoding is canonical.
+#[derive(Clone, PartialEq, Debug, Default, Encode, Decode)]
+pub struct CheckpointStates<C: Config> {
+    states: Vec<CheckpointState<C>>,
+}
+
+#[derive(Clone, PartialEq, Debug, Encode, Decode)]
+struct CheckpointState<C: Config> {
+    checkpoint: Checkpoint,
+    state: BeaconState<C>,
+}
+
+impl<C: Config> From<HashMap<Checkpoint, BeaconState<C>>> for CheckpointStates<C> {
+    fn from(map: HashMap<Checkpoint, BeaconState<C>>) -> Self {
+        let mut states = map
+            .into_iter()
+            .map(|(checkpoint, state)| CheckpointState { checkpoint, state })
+            .collect::<Vec<_>>();
+        states
+            .sort_by_key(|CheckpointState { checkpoint, .. }| (checkpoint.epoch, checkpoint.root));
+        Self { states }
+    }
+}
+
+impl<C: Config> From<CheckpointStates<C>> for HashMap<Checkpoint, BeaconState<C>> {
+    fn from(checkpoint_states: CheckpointStates<C>) -> Self {
+        checkpoint_states
+            .states
+            .into_iter()
+            .map(|CheckpointState { checkpoint, state }| (checkpoint, state))
+            .collect()
+    }
 }
 
 /// <https://github.com/ethereum/eth2.0-specs/blob/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#store>
@@ -51,14 +257,40 @@ pub struct Store<C: Config> {
     finalized_checkpoint: Checkpoint,
     // `blocks` and `block_states` could be combined into a single map.
     // We've left them separate to match the specification more closely.
-    blocks: HashMap<H256, BeaconBlock<C>>,
+    blocks: HashMap<H256, SignedBeaconBlock<C>>,
     block_states: HashMap<H256, BeaconState<C>>,
     checkpoint_states: HashMap<Checkpoint, BeaconState<C>>,
     latest_messages: HashMap<ValidatorIndex, LatestMessage>,
 
+    // Attestations that updated at least one latest message, grouped by target epoch.
+    // Used to construct proofs for light clients. Epochs before finalization are pruned.
+    attestations: BTreeMap<Epoch, Vec<Attestation<C>>>,
+
+    // Messages ignored because the validator had already voted for another block in the same
+    // epoch. Epochs before finalization are pruned.
+    conflicting_messages: Vec<ConflictingMessage>,
+
+    // Attestations whose signatures will be checked by `Store::verify_pending_attestations`.
+    // They do not affect the head until then.
+    unverified_attestations: Vec<UnverifiedAttestation<C>>,
+
+    // The root of the head block as of the last call to `Store::head`.
+    // Cleared whenever a block or attestation that may change the head is accepted.
+    cached_head: Cell<Option<H256>>,
+    // The root returned by the last call to `Store::head`. Unlike `cached_head`, it is never
+    // cleared, so `Store::on_block` can compare against it without recomputing the old head.
+    last_head: Cell<H256>,
+
     // Extra fields used for delaying and retrying objects.
     delayed_until_block: HashMap<H256, Vec<DelayedObject<C>>>,
     delayed_until_slot: BTreeMap<Slot, Vec<DelayedObject<C>>>,
+    // The number of delayed blocks discarded because finalization made them obsolete.
+    dropped_delayed_blocks: usize,
+    // Blocks more than this many slots ahead of `slot` are rejected instead of being delayed.
+    max_future_slots: Slot,
+
+    // Inputs are only recorded if the `Store` was created with `Store::with_event_log`.
+    event_log: Option<StoreEventLog<C>>,
 }
 
 impl<C: Config> Store<C> {
@@ -68,16 +300,20 @@ impl<C: Config> Store<C> {
         // independently produce the same block. But why does the genesis block have to
         // exist at all? Perhaps the first block could be proposed by a validator as well
         // (and not necessarily in slot 0)?
-        let genesis_block = BeaconBlock {
-            // Note that:
-            // - `BeaconBlock.body.eth1_data` is not set to `state.latest_eth1_data`.
-            // - `BeaconBlock.slot` is set to 0 even if `C::genesis_slot()` is not 0.
-            state_root: crypto::hash_tree_root(&genesis_state),
-            ..BeaconBlock::default()
+        let genesis_block = SignedBeaconBlock {
+            message: BeaconBlock {
+                // Note that:
+                // - `BeaconBlock.body.eth1_data` is not set to `state.latest_eth1_data`.
+                // - `BeaconBlock.slot` is set to 0 even if `C::genesis_slot()` is not 0.
+                state_root: crypto::hash_tree_root(&genesis_state),
+                ..BeaconBlock::default()
+            },
+            // The genesis block is not signed by anyone.
+            ..SignedBeaconBlock::default()
         };
 
         let epoch = C::genesis_epoch();
-        let root = crypto::signed_root(&genesis_block);
+        let root = crypto::hash_tree_root(&genesis_block.message);
         let checkpoint = Checkpoint { epoch, root };
 
         Self {
@@ -88,17 +324,176 @@ impl<C: Config> Store<C> {
             block_states: hashmap! {root => genesis_state.clone()},
             checkpoint_states: hashmap! {checkpoint => genesis_state},
             latest_messages: hashmap! {},
+            attestations: BTreeMap::new(),
+            conflicting_messages: vec![],
+            unverified_attestations: vec![],
+
+            cached_head: Cell::new(None),
+            last_head: Cell::new(root),
 
             delayed_until_slot: BTreeMap::new(),
             delayed_until_block: HashMap::new(),
+            dropped_delayed_blocks: 0,
+            max_future_slots: DEFAULT_MAX_FUTURE_SLOTS,
+
+            event_log: None,
+        }
+    }
+
+    /// Like [`Store::new`], but records all subsequent inputs in a [`StoreEventLog`].
+    pub fn with_event_log(genesis_state: BeaconState<C>) -> Self {
+        let event_log = StoreEventLog {
+            anchor: genesis_state.clone(),
+            events: vec![],
+        };
+        Self {
+            event_log: Some(event_log),
+            ..Self::new(genesis_state)
+        }
+    }
+
+    /// Creates a new `Store` from `event_log.anchor` and feeds it the recorded events in order.
+    ///
+    /// Errors do not stop the replay, because the original `Store` kept going after rejecting an
+    /// object as well. The result of processing the last event is returned alongside the `Store`
+    /// so that an error that ended a session can be reproduced.
+    ///
+    /// The returned `Store` records events as well, so a replay can be replayed again.
+    pub fn replay(event_log: &StoreEventLog<C>) -> (Self, Result<()>) {
+        let mut store = Self::with_event_log(event_log.anchor.clone());
+        let mut last_result = Ok(());
+        for event in event_log.events.iter().cloned() {
+            last_result = match event {
+                StoreEvent::Slot(slot) => store.on_slot(slot),
+                StoreEvent::BeaconBlock(block) => store.on_block(block).map(|_| ()),
+                StoreEvent::Attestation(attestation) => store.on_attestation(attestation),
+                StoreEvent::UnverifiedAttestation(attestation) => {
+                    store.on_unverified_attestation(attestation)
+                }
+                StoreEvent::VerifyPendingAttestations => {
+                    store.verify_pending_attestations().map(|_| ())
+                }
+            };
+            if let Err(error) = &last_result {
+                info!("replayed event was rejected: {}", error);
+            }
+        }
+        (store, last_result)
+    }
+
+    pub fn event_log(&self) -> Option<&StoreEventLog<C>> {
+        self.event_log.as_ref()
+    }
+
+    /// Sets how many slots ahead of the `Store` a block may be before it is rejected.
+    ///
+    /// Defaults to [`DEFAULT_MAX_FUTURE_SLOTS`].
+    pub fn set_max_future_slots(&mut self, max_future_slots: Slot) {
+        self.max_future_slots = max_future_slots;
+    }
+
+    /// Replaces all latest messages with `messages`.
+    ///
+    /// Messages that refer to unknown blocks are dropped because they cannot affect the head.
+    pub fn set_latest_messages(&mut self, messages: HashMap<ValidatorIndex, Checkpoint>) {
+        let blocks = &self.blocks;
+        self.latest_messages = messages
+            .into_iter()
+            .filter(|(_, message)| blocks.contains_key(&message.root))
+            .collect();
+        self.cached_head.set(None);
+    }
+
+    pub fn to_persisted(&self) -> PersistedStore<C> {
+        let mut roots = self.blocks.keys().copied().collect::<Vec<_>>();
+
+        // Sort the blocks to make the encoding deterministic. Parents come before their children.
+        roots.sort_by_key(|root| (self.blocks[root].message.slot, *root));
+
+        let blocks = roots
+            .into_iter()
+            .map(|root| PersistedBlock {
+                block: self.blocks[&root].clone(),
+                state: self.block_states[&root].clone(),
+            })
+            .collect();
+
+        let mut latest_messages = self
+            .latest_messages
+            .iter()
+            .map(|(&validator_index, &message)| PersistedLatestMessage {
+                validator_index,
+                message,
+            })
+            .collect::<Vec<_>>();
+
+        latest_messages.sort_by_key(|persisted| persisted.validator_index);
+
+        PersistedStore {
+            slot: self.slot,
+            justified_checkpoint: self.justified_checkpoint,
+            finalized_checkpoint: self.finalized_checkpoint,
+            blocks,
+            checkpoint_states: self.checkpoint_states.clone().into(),
+            latest_messages,
         }
     }
 
+    pub fn from_persisted(persisted: PersistedStore<C>) -> Result<Self> {
+        let mut blocks = HashMap::with_capacity(persisted.blocks.len());
+        let mut block_states = HashMap::with_capacity(persisted.blocks.len());
+
+        for PersistedBlock { block, state } in persisted.blocks {
+            let root = crypto::hash_tree_root(&block.message);
+            blocks.insert(root, block);
+            block_states.insert(root, state);
+        }
+
+        let mut store = Self {
+            slot: persisted.slot,
+            justified_checkpoint: persisted.justified_checkpoint,
+            finalized_checkpoint: persisted.finalized_checkpoint,
+            blocks,
+            block_states,
+            checkpoint_states: persisted.checkpoint_states.into(),
+            latest_messages: hashmap! {},
+            attestations: BTreeMap::new(),
+            conflicting_messages: vec![],
+            unverified_attestations: vec![],
+
+            cached_head: Cell::new(None),
+            last_head: Cell::new(persisted.justified_checkpoint.root),
+
+            delayed_until_slot: BTreeMap::new(),
+            delayed_until_block: HashMap::new(),
+            dropped_delayed_blocks: 0,
+            max_future_slots: DEFAULT_MAX_FUTURE_SLOTS,
+
+            event_log: None,
+        };
+
+        // These are no-ops unless the persisted checkpoint states are incomplete.
+        store.store_checkpoint_state(store.justified_checkpoint)?;
+        store.store_checkpoint_state(store.finalized_checkpoint)?;
+        store.set_latest_messages(
+            persisted
+                .latest_messages
+                .into_iter()
+                .map(|persisted| (persisted.validator_index, persisted.message))
+                .collect(),
+        );
+        store.head()?;
+        Ok(store)
+    }
+
     /// <https://github.com/ethereum/eth2.0-specs/blob/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#get_head>
     ///
-    /// Unlike the `get_head` function in the specification, this returns the [`BeaconState`]
-    /// produced after processing the current head block.
-    pub fn head_state(&self) -> &BeaconState<C> {
+    /// The result is cached until the next block or attestation is accepted.
+    pub fn head(&self) -> Result<H256> {
+        if let Some(head_root) = self.cached_head.get() {
+            return Ok(head_root);
+        }
+
         let mut current_root = self.justified_checkpoint.root;
 
         let justified_slot = Self::epoch_start_slot(self.justified_checkpoint.epoch);
@@ -106,9 +501,10 @@ impl<C: Config> Store<C> {
         let head_root = loop {
             let mut child_with_plurality = None;
 
-            for (&root, block) in &self.blocks {
+            for (&root, signed_block) in &self.blocks {
+                let block = &signed_block.message;
                 if block.parent_root == current_root && justified_slot < block.slot {
-                    let balance = self.latest_attesting_balance(root, block);
+                    let balance = self.latest_attesting_balance(root, block)?;
                     child_with_plurality = Some((balance, root)).max(child_with_plurality);
                 }
             }
@@ -119,7 +515,158 @@ impl<C: Config> Store<C> {
             }
         };
 
-        &self.block_states[&head_root]
+        self.cached_head.set(Some(head_root));
+        self.last_head.set(head_root);
+
+        Ok(head_root)
+    }
+
+    /// Returns the latest attesting balance of every block considered by [`Store::head`].
+    ///
+    /// This is meant for debugging fork choice decisions.
+    pub fn block_weights(&self) -> Result<HashMap<H256, Gwei>> {
+        let justified_root = self.justified_checkpoint.root;
+        let justified_block = &self.blocks[&justified_root].message;
+        let justified_slot = Self::epoch_start_slot(self.justified_checkpoint.epoch);
+
+        self.blocks
+            .iter()
+            .map(|(&root, signed_block)| (root, &signed_block.message))
+            .filter(|&(root, block)| {
+                root == justified_root
+                    || justified_slot < block.slot
+                        && self.ancestor(root, block, justified_block.slot) == Ok(justified_root)
+            })
+            .map(|(root, block)| {
+                self.latest_attesting_balance(root, block)
+                    .map(|weight| (root, weight))
+            })
+            .collect()
+    }
+
+    /// Returns the blocks considered by [`Store::head`] with their weights, in order of increasing
+    /// slot. Meant for visualizing the block tree.
+    pub fn viable_tree_snapshot(&self) -> Result<Vec<BlockNode>> {
+        let mut nodes = self
+            .block_weights()?
+            .into_iter()
+            .map(|(root, weight)| {
+                let block = &self.blocks[&root].message;
+                BlockNode {
+                    root,
+                    parent_root: block.parent_root,
+                    slot: block.slot,
+                    weight,
+                }
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|node| (node.slot, node.root));
+        Ok(nodes)
+    }
+
+    /// Returns the latest attesting balance of the head block, which is the weight [`Store::head`]
+    /// compared against its siblings.
+    ///
+    /// This version of fork choice has no proposer boost and does not discount equivocating
+    /// validators, so the weight consists of latest messages alone.
+    pub fn head_support(&self) -> Result<Gwei> {
+        let head_root = self.head()?;
+        self.latest_attesting_balance(head_root, &self.blocks[&head_root].message)
+    }
+
+    pub fn debug_dump(&self) -> Result<ForkChoiceDump> {
+        let mut roots = self.blocks.keys().copied().collect::<Vec<_>>();
+        roots.sort_by_key(|root| (self.blocks[root].message.slot, *root));
+
+        let edges = roots
+            .into_iter()
+            .map(|root| (root, self.blocks[&root].message.parent_root))
+            .collect();
+
+        let mut latest_message_counts = BTreeMap::new();
+        for message in self.latest_messages.values() {
+            *latest_message_counts.entry(message.root).or_default() += 1;
+        }
+
+        Ok(ForkChoiceDump {
+            slot: self.slot,
+            justified_checkpoint: self.justified_checkpoint,
+            finalized_checkpoint: self.finalized_checkpoint,
+            head: self.head()?,
+            edges,
+            weights: self.block_weights()?.into_iter().collect(),
+            latest_message_counts,
+        })
+    }
+
+    /// Returns the balance of validators whose attestations in the head state support justifying
+    /// the current epoch, along with the total active balance.
+    ///
+    /// The current epoch is justified once the former reaches 2/3 of the latter.
+    pub fn justification_progress(&self) -> Result<(Gwei, Gwei)> {
+        let head_state = self.head_state()?;
+        let total_balance = beacon_state_accessors::get_total_active_balance(head_state)
+            .map_err(DebugAsError::new)?;
+        let current_epoch = beacon_state_accessors::get_current_epoch(head_state);
+
+        // The block root for the current epoch is not available in the first slot of the epoch.
+        // No attestations from the current epoch can have been included by then anyway.
+        if head_state.slot == Self::epoch_start_slot(current_epoch) {
+            return Ok((0, total_balance));
+        }
+
+        let target_attestations = head_state.get_matching_target_attestations(current_epoch);
+        let supporting_balance = head_state.get_attesting_balance(target_attestations);
+
+        Ok((supporting_balance, total_balance))
+    }
+
+    /// Returns the header of the head block along with the attestations from the epoch of the
+    /// head block that vote for it. A light client can tally the attestations to check that the
+    /// head has more support than its siblings.
+    pub fn head_proof(&self) -> Result<(BeaconBlockHeader, Vec<Attestation<C>>)> {
+        let head_root = self.head()?;
+        let head_block = &self.blocks[&head_root].message;
+        let head_epoch = misc::compute_epoch_at_slot::<C>(head_block.slot);
+
+        let header = BeaconBlockHeader {
+            slot: head_block.slot,
+            parent_root: head_block.parent_root,
+            state_root: head_block.state_root,
+            body_root: crypto::hash_tree_root(&head_block.body),
+        };
+
+        let attestations = self
+            .attestations
+            .get(&head_epoch)
+            .into_iter()
+            .flatten()
+            .filter(|attestation| {
+                let root = attestation.data.beacon_block_root;
+                self.blocks.get(&root).map_or(false, |block| {
+                    self.ancestor(root, &block.message, head_block.slot) == Ok(head_root)
+                })
+            })
+            .cloned()
+            .collect();
+
+        Ok((header, attestations))
+    }
+
+    /// Unlike the `get_head` function in the specification, this returns the [`BeaconState`]
+    /// produced after processing the current head block.
+    pub fn head_state(&self) -> Result<&BeaconState<C>> {
+        Ok(&self.block_states[&self.head()?])
+    }
+
+    /// Returns the state produced by processing empty slots after the head block up to `slot`.
+    ///
+    /// [`Store::head_state`] lags behind the current slot when no blocks arrive. Proposers and
+    /// committees for an empty slot should be computed from the state returned by this instead.
+    pub fn head_state_at_slot(&self, slot: Slot) -> Result<BeaconState<C>> {
+        let mut state = self.head_state()?.clone();
+        process_slot::process_slots(&mut state, slot)?;
+        Ok(state)
     }
 
     /// <https://github.com/ethereum/eth2.0-specs/blob/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#on_tick>
@@ -127,6 +674,7 @@ impl<C: Config> Store<C> {
     /// Unlike `on_tick` in the specification, this should be called at the start of a slot instead
     /// of every second. The fork choice rule doesn't need a precise timestamp.
     pub fn on_slot(&mut self, slot: Slot) -> Result<()> {
+        self.record(|| StoreEvent::Slot(slot));
         ensure!(
             self.slot < slot,
             Error::<C>::SlotNotLater {
@@ -135,11 +683,101 @@ impl<C: Config> Store<C> {
             },
         );
         self.slot = slot;
-        self.retry_delayed_until_slot(slot)
+        self.retry_delayed_until_slot(slot)?;
+        self.refresh_last_head()
     }
 
     /// <https://github.com/ethereum/eth2.0-specs/blob/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#on_block>
-    pub fn on_block(&mut self, block: BeaconBlock<C>) -> Result<()> {
+    ///
+    /// Returns a [`ReorgEvent`] if the new head is not a descendant of the previous head.
+    /// The head is only recomputed if the block was applied rather than delayed or ignored.
+    pub fn on_block(&mut self, signed_block: SignedBeaconBlock<C>) -> Result<Option<ReorgEvent>> {
+        self.record(|| StoreEvent::BeaconBlock(signed_block.clone()));
+        let old_head = self.last_head.get();
+
+        if !self.handle_block(signed_block)? {
+            return Ok(None);
+        }
+
+        let new_head = self.head()?;
+
+        if new_head == old_head {
+            return Ok(None);
+        }
+
+        let (common_ancestor, common_ancestor_slot) = self.common_ancestor(old_head, new_head);
+
+        if common_ancestor == old_head {
+            return Ok(None);
+        }
+
+        Ok(Some(ReorgEvent {
+            old_head,
+            new_head,
+            common_ancestor_slot,
+        }))
+    }
+
+    /// Calls [`Store::on_block`] and returns the resulting head.
+    ///
+    /// The head is unchanged if the block was delayed.
+    pub fn process_block_and_head(&mut self, signed_block: SignedBeaconBlock<C>) -> Result<H256> {
+        self.on_block(signed_block)?;
+        // This is served from the cache if `Store::on_block` applied the block.
+        self.head()
+    }
+
+    /// <https://github.com/ethereum/eth2.0-specs/blob/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#on_attestation>
+    pub fn on_attestation(&mut self, attestation: Attestation<C>) -> Result<()> {
+        self.record(|| StoreEvent::Attestation(attestation.clone()));
+        self.handle_attestation(attestation, true)?;
+        self.refresh_last_head()
+    }
+
+    /// Like [`Store::on_attestation`], but defers the signature check to
+    /// [`Store::verify_pending_attestations`].
+    ///
+    /// The attestation does not affect the head until its signature is verified.
+    pub fn on_unverified_attestation(&mut self, attestation: Attestation<C>) -> Result<()> {
+        self.record(|| StoreEvent::UnverifiedAttestation(attestation.clone()));
+        self.handle_attestation(attestation, false)
+    }
+
+    /// Verifies the signatures of all attestations passed to [`Store::on_unverified_attestation`]
+    /// since the last call and applies the valid ones.
+    ///
+    /// Returns the attestations that were rejected.
+    pub fn verify_pending_attestations(&mut self) -> Result<Vec<Attestation<C>>> {
+        self.record(|| StoreEvent::VerifyPendingAttestations);
+
+        let mut rejected = vec![];
+
+        for unverified in mem::replace(&mut self.unverified_attestations, vec![]) {
+            let UnverifiedAttestation {
+                attestation,
+                indexed_attestation,
+            } = unverified;
+            let target_state = &self.checkpoint_states[&attestation.data.target];
+            match predicates::validate_indexed_attestation(target_state, &indexed_attestation) {
+                Ok(()) => self.apply_attestation(attestation, &indexed_attestation),
+                Err(error) => {
+                    info!("rejected attestation ({:?}): {:?}", error, attestation);
+                    rejected.push(attestation);
+                }
+            }
+        }
+
+        self.refresh_last_head()?;
+
+        Ok(rejected)
+    }
+
+    // Delayed objects are passed to `Store::handle_block` and `Store::handle_attestation` directly
+    // to avoid recording them in the event log a second time.
+    // Returns `true` if `signed_block` was added to the store.
+    fn handle_block(&mut self, signed_block: SignedBeaconBlock<C>) -> Result<bool> {
+        let block = &signed_block.message;
+
         // The specification uses 2 different ways to calculate what appears to be the same value:
         // - <https://github.com/ethereum/eth2.0-specs/blame/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#L155>
         // - <https://github.com/ethereum/eth2.0-specs/blame/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#L159>
@@ -150,124 +788,338 @@ impl<C: Config> Store<C> {
         // - The genesis block is accepted even though it does not represent a state transition.
         // - Blocks that are already known and are received again are always accepted.
         if block.slot <= finalized_slot {
-            return Ok(());
+            return Ok(false);
         }
 
+        // Delaying a block like this would waste memory on an object that may never be retried.
+        ensure!(
+            block.slot <= self.slot.saturating_add(self.max_future_slots),
+            Error::<C>::BlockTooFarInFuture {
+                slot: self.slot,
+                block_slot: block.slot,
+                max_future_slots: self.max_future_slots,
+            },
+        );
+
         let parent_state = if let Some(state) = self.block_states.get(&block.parent_root) {
             state
         } else {
-            self.delay_until_block(block.parent_root, DelayedObject::BeaconBlock(block));
-            return Ok(());
+            self.delay_until_block(block.parent_root, DelayedObject::BeaconBlock(signed_block));
+            return Ok(false);
         };
 
         if self.slot < block.slot {
-            self.delay_until_slot(block.slot, DelayedObject::BeaconBlock(block));
-            return Ok(());
+            self.delay_until_slot(block.slot, DelayedObject::BeaconBlock(signed_block));
+            return Ok(false);
         }
 
-        let block_root = crypto::signed_root(&block);
+        let block_root = crypto::hash_tree_root(block);
+
+        // A block that has already been accepted passed all of the checks below. Cloning the parent
+        // state and running the state transition again would only waste time.
+        // The signature is compared too because a copy with an invalid signature should still fail.
+        if self.blocks.get(&block_root) == Some(&signed_block) {
+            return Ok(false);
+        }
+
+        // The parent is known at this point, but an earlier ancestor may not be if the store was
+        // restored from an incomplete set of blocks. Wait for it instead of panicking.
+        let finalized_ancestor = match self.ancestor(block_root, block, finalized_slot) {
+            Ok(ancestor) => ancestor,
+            Err(MissingBlock { root }) => {
+                self.delay_until_block(root, DelayedObject::BeaconBlock(signed_block));
+                return Ok(false);
+            }
+        };
 
         ensure!(
-            self.ancestor(block_root, &block, finalized_slot) == self.finalized_checkpoint.root,
+            finalized_ancestor == self.finalized_checkpoint.root,
             Error::NotDescendantOfFinalized {
-                block,
+                block: signed_block,
                 finalized_block: self.blocks[&self.finalized_checkpoint.root].clone(),
             },
         );
 
         let mut state = parent_state.clone();
-        process_slot::state_transition(&mut state, &block, true);
+        process_slot::state_transition(&mut state, &signed_block, true)?;
         let state = self.block_states.entry(block_root).or_insert(state);
+        let justified_checkpoint = state.current_justified_checkpoint;
+        let finalized_checkpoint = state.finalized_checkpoint;
 
         // Add `block` to `self.blocks` only when it's passed all checks.
         // See <https://github.com/ethereum/eth2.0-specs/issues/1288>.
-        self.blocks.insert(block_root, block);
+        self.blocks.insert(block_root, signed_block);
+        self.cached_head.set(None);
+
+        self.update_checkpoints(justified_checkpoint, finalized_checkpoint)?;
+
+        self.retry_delayed_until_block(block_root)?;
+
+        Ok(true)
+    }
 
-        if self.justified_checkpoint.epoch < state.current_justified_checkpoint.epoch {
-            self.justified_checkpoint = state.current_justified_checkpoint;
+    // The states of both checkpoints must already be in `self.block_states`.
+    fn update_checkpoints(
+        &mut self,
+        justified_checkpoint: Checkpoint,
+        finalized_checkpoint: Checkpoint,
+    ) -> Result<()> {
+        // `Store::head` reads the state of the justified checkpoint, so it has to be stored as soon
+        // as justification advances rather than only when the checkpoint is finalized.
+        if self.justified_checkpoint.epoch < justified_checkpoint.epoch {
+            self.justified_checkpoint = justified_checkpoint;
+            self.store_checkpoint_state(self.justified_checkpoint)?;
         }
 
-        if self.finalized_checkpoint.epoch < state.finalized_checkpoint.epoch {
-            self.finalized_checkpoint = state.finalized_checkpoint;
+        if self.finalized_checkpoint.epoch < finalized_checkpoint.epoch {
+            self.finalized_checkpoint = finalized_checkpoint;
+            self.store_checkpoint_state(self.finalized_checkpoint)?;
+            self.drop_stale_delayed_blocks();
+            self.attestations = self
+                .attestations
+                .split_off(&self.finalized_checkpoint.epoch);
+            let finalized_epoch = self.finalized_checkpoint.epoch;
+            self.conflicting_messages
+                .retain(|conflict| finalized_epoch <= conflict.latest_message.epoch);
         }
 
-        self.retry_delayed_until_block(block_root)
+        Ok(())
     }
 
-    /// <https://github.com/ethereum/eth2.0-specs/blob/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#on_attestation>
-    pub fn on_attestation(&mut self, attestation: Attestation<C>) -> Result<()> {
+    fn handle_attestation(
+        &mut self,
+        attestation: Attestation<C>,
+        verify_signature: bool,
+    ) -> Result<()> {
         let target = attestation.data.target;
 
+        let delayed = if verify_signature {
+            DelayedObject::Attestation
+        } else {
+            DelayedObject::UnverifiedAttestation
+        };
+
+        // `target.root` is the root of the latest block at or before the start of the target
+        // epoch, so no block can exist between it and `target_epoch_start` on the same chain.
+        // Processing empty slots from the target block's state is therefore enough to produce the
+        // state at the start of the target epoch, as in the specification.
         let base_state = if let Some(state) = self.block_states.get(&target.root) {
             state
         } else {
-            self.delay_until_block(target.root, DelayedObject::Attestation(attestation));
+            self.delay_until_block(target.root, delayed(attestation));
             return Ok(());
         };
 
-        let target_epoch_start = Self::epoch_start_slot(target.epoch);
+        // The target epoch of an attestation received from the network may be arbitrarily large.
+        let target_epoch_start = misc::compute_start_slot_at_epoch_checked::<C>(target.epoch)
+            .ok_or(Error::<C>::EpochTooLarge {
+                epoch: target.epoch,
+            })?;
 
         if self.slot < target_epoch_start {
-            self.delay_until_slot(target_epoch_start, DelayedObject::Attestation(attestation));
+            self.delay_until_slot(target_epoch_start, delayed(attestation));
             return Ok(());
         }
 
-        let target_state = self.checkpoint_states.entry(target).or_insert_with(|| {
-            let mut target_state = base_state.clone();
-            process_slot::process_slots(&mut target_state, target_epoch_start);
-            target_state
-        });
+        let target_state = match self.checkpoint_states.entry(target) {
+            Entry::Occupied(occupied) => occupied.into_mut(),
+            Entry::Vacant(vacant) => vacant.insert(Self::checkpoint_state(base_state, target)?),
+        };
 
         if self.slot <= attestation.data.slot {
-            self.delay_until_slot(
-                attestation.data.slot,
-                DelayedObject::Attestation(attestation),
-            );
+            self.delay_until_slot(attestation.data.slot, delayed(attestation));
+            return Ok(());
+        }
+
+        let indexed_attestation =
+            beacon_state_accessors::get_indexed_attestation(target_state, &attestation)
+                .map_err(DebugAsError::new)?;
+
+        if !verify_signature {
+            self.unverified_attestations.push(UnverifiedAttestation {
+                attestation,
+                indexed_attestation,
+            });
             return Ok(());
         }
 
+        match predicates::validate_indexed_attestation(target_state, &indexed_attestation) {
+            Ok(()) => {}
+            Err(HelperError::InvalidSignature) => {
+                bail!(Error::InvalidAttestationSignature { attestation })
+            }
+            Err(error) => return Err(DebugAsError::new(error).into()),
+        }
+
+        self.apply_attestation(attestation, &indexed_attestation);
+
+        Ok(())
+    }
+
+    fn apply_attestation(
+        &mut self,
+        attestation: Attestation<C>,
+        indexed_attestation: &IndexedAttestation<C>,
+    ) {
+        let target = attestation.data.target;
+
         let new_message = LatestMessage {
             epoch: target.epoch,
             root: attestation.data.beacon_block_root,
         };
 
-        let indexed_attestation =
-            beacon_state_accessors::get_indexed_attestation(target_state, &attestation)
-                .map_err(DebugAsError::new)?;
-
-        predicates::validate_indexed_attestation(target_state, &indexed_attestation)
-            .map_err(DebugAsError::new)?;
+        let mut updated_any = false;
 
         for index in indexed_attestation.attesting_indices.iter().copied() {
-            let old_message = self.latest_messages.entry(index).or_default();
+            let old_message = match self.latest_messages.entry(index) {
+                Entry::Occupied(occupied) => occupied.into_mut(),
+                Entry::Vacant(vacant) => {
+                    vacant.insert(new_message);
+                    updated_any = true;
+                    continue;
+                }
+            };
             if old_message.epoch < new_message.epoch {
                 *old_message = new_message;
+                updated_any = true;
+            } else if old_message.epoch == new_message.epoch && old_message.root != new_message.root
+            {
+                let conflict = ConflictingMessage {
+                    validator_index: index,
+                    latest_message: *old_message,
+                    conflicting_message: new_message,
+                };
+                if !self.conflicting_messages.contains(&conflict) {
+                    info!("ignored conflicting message: {:?}", conflict);
+                    self.conflicting_messages.push(conflict);
+                }
             }
         }
 
-        Ok(())
+        if updated_any {
+            self.attestations
+                .entry(target.epoch)
+                .or_default()
+                .push(attestation);
+        }
+
+        self.cached_head.set(None);
     }
 
-    pub fn block(&self, root: H256) -> Option<&BeaconBlock<C>> {
-        self.blocks.get(&root)
+    /// Returns the messages ignored by [`Store::on_attestation`] because the validator had already
+    /// voted for a different block with the same target epoch, in the order they were received.
+    pub fn conflicting_messages(&self) -> &[ConflictingMessage] {
+        &self.conflicting_messages
     }
 
-    /// <https://github.com/ethereum/eth2.0-specs/blob/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#get_latest_attesting_balance>
+    /// Returns the state at the start of the epoch of `self.finalized_checkpoint`.
     ///
-    /// The extra `block` parameter is used to avoid a redundant block lookup.
-    fn latest_attesting_balance(&self, root: H256, block: &BeaconBlock<C>) -> Gwei {
-        let justified_state = &self.checkpoint_states[&self.justified_checkpoint];
-        let active_indices = beacon_state_accessors::get_active_validator_indices(
-            justified_state,
+    /// The state is stored whenever the finalized checkpoint changes, so this should never fail.
+    pub fn finalized_checkpoint_state(&self) -> Result<&BeaconState<C>> {
+        self.stored_checkpoint_state(self.finalized_checkpoint)
+    }
+
+    /// Returns the first slot in which `attestation` can be included in a block.
+    pub fn earliest_inclusion_slot(&self, attestation: &Attestation<C>) -> Slot {
+        attestation.data.slot + C::min_attestation_inclusion_delay()
+    }
+
+    /// Returns whether a block in the current slot may include `attestation`.
+    ///
+    /// This uses the same inclusion window as `process_attestation`.
+    pub fn is_includable_now(&self, attestation: &Attestation<C>) -> bool {
+        self.earliest_inclusion_slot(attestation) <= self.slot
+            && self.slot <= attestation.data.slot + C::SlotsPerEpoch::U64
+    }
+
+    /// Returns the number of delayed blocks discarded because they could never be processed.
+    pub fn dropped_delayed_blocks(&self) -> usize {
+        self.dropped_delayed_blocks
+    }
+
+    pub fn block(&self, root: H256) -> Option<&SignedBeaconBlock<C>> {
+        self.blocks.get(&root)
+    }
+
+    /// Returns the SSZ encodings of blocks in the canonical chain with slots in
+    /// `start_slot..end_slot`, in order of increasing slot. Empty slots are skipped.
+    pub fn encode_canonical_blocks(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<Vec<Vec<u8>>> {
+        let mut encoded = vec![];
+        let mut root = self.head()?;
+
+        while let Some(signed_block) = self.blocks.get(&root) {
+            let block = &signed_block.message;
+            if block.slot < start_slot {
+                break;
+            }
+            if block.slot < end_slot {
+                encoded.push(signed_block.as_ssz_bytes());
+            }
+            root = block.parent_root;
+        }
+
+        encoded.reverse();
+        Ok(encoded)
+    }
+
+    /// Returns all known blocks at `slot`, including ones not in the canonical chain.
+    pub fn blocks_at_slot(&self, slot: Slot) -> Vec<(H256, &SignedBeaconBlock<C>)> {
+        let mut blocks = self
+            .blocks
+            .iter()
+            .filter(|(_, block)| block.message.slot == slot)
+            .map(|(root, block)| (*root, block))
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|(root, _)| *root);
+        blocks
+    }
+
+    /// Returns the roots of known blocks after the finalized block that are not in the canonical
+    /// chain, in order of increasing slot.
+    pub fn orphaned_blocks(&self) -> Result<Vec<H256>> {
+        let finalized_slot = self.blocks[&self.finalized_checkpoint.root].message.slot;
+
+        let mut canonical = HashSet::new();
+        let mut root = self.head()?;
+        while let Some(block) = self.blocks.get(&root) {
+            if block.message.slot <= finalized_slot {
+                break;
+            }
+            canonical.insert(root);
+            root = block.message.parent_root;
+        }
+
+        let mut orphaned = self
+            .blocks
+            .iter()
+            .map(|(root, block)| (block.message.slot, *root))
+            .filter(|(slot, root)| *slot > finalized_slot && !canonical.contains(root))
+            .collect::<Vec<_>>();
+        orphaned.sort();
+        Ok(orphaned.into_iter().map(|(_, root)| root).collect())
+    }
+
+    /// <https://github.com/ethereum/eth2.0-specs/blob/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#get_latest_attesting_balance>
+    ///
+    /// The extra `block` parameter is used to avoid a redundant block lookup.
+    fn latest_attesting_balance(&self, root: H256, block: &BeaconBlock<C>) -> Result<Gwei> {
+        let justified_state = self.stored_checkpoint_state(self.justified_checkpoint)?;
+        let active_indices = beacon_state_accessors::get_active_validator_indices(
+            justified_state,
             beacon_state_accessors::get_current_epoch(justified_state),
         );
 
-        active_indices
+        let balance = active_indices
             .into_iter()
             .filter_map(|index| {
                 let latest_message = self.latest_messages.get(&index)?;
-                let latest_message_block = &self.blocks[&latest_message.root];
-                if self.ancestor(latest_message.root, latest_message_block, block.slot) == root {
+                let latest_message_block = &self.blocks[&latest_message.root].message;
+                let ancestor = self.ancestor(latest_message.root, latest_message_block, block.slot);
+                if ancestor == Ok(root) {
                     // The `Result::expect` call would be avoidable if there were a function like
                     // `beacon_state_accessors::get_active_validator_indices` that returned
                     // references to the validators in addition to their indices.
@@ -279,30 +1131,99 @@ impl<C: Config> Store<C> {
                     None
                 }
             })
-            .sum()
+            .sum();
+
+        Ok(balance)
     }
 
     /// <https://github.com/ethereum/eth2.0-specs/blob/65b615a4d4cf75a50b29d25c53f1bc5422770ae5/specs/core/0_fork-choice.md#get_ancestor>
     ///
     /// The extra `block` parameter is used to avoid adding `block` to `self.blocks` before
     /// verifying it. See <https://github.com/ethereum/eth2.0-specs/issues/1288>.
-    /// The parent of `block` must still be present in `self.blocks`, however.
-    fn ancestor(&self, root: H256, block: &BeaconBlock<C>, slot: Slot) -> H256 {
+    /// Returns the root of the first block missing from `self.blocks` if the walk reaches one.
+    fn ancestor(
+        &self,
+        root: H256,
+        block: &BeaconBlock<C>,
+        slot: Slot,
+    ) -> core::result::Result<H256, MissingBlock> {
         match block.slot.cmp(&slot) {
-            Ordering::Less => H256::zero(),
-            Ordering::Equal => root,
+            Ordering::Less => Ok(H256::zero()),
+            Ordering::Equal => Ok(root),
             Ordering::Greater => {
                 let parent_root = block.parent_root;
-                let parent_block = &self.blocks[&block.parent_root];
+                let parent_block = &self
+                    .blocks
+                    .get(&parent_root)
+                    .ok_or(MissingBlock { root: parent_root })?
+                    .message;
                 self.ancestor(parent_root, parent_block, slot)
             }
         }
     }
 
+    /// Returns the root and slot of the latest block that both `a` and `b` descend from.
+    fn common_ancestor(&self, mut a: H256, mut b: H256) -> (H256, Slot) {
+        loop {
+            let a_block = &self.blocks[&a].message;
+            let b_block = &self.blocks[&b].message;
+            match a_block.slot.cmp(&b_block.slot) {
+                Ordering::Less => b = b_block.parent_root,
+                Ordering::Greater => a = a_block.parent_root,
+                Ordering::Equal if a == b => break (a, a_block.slot),
+                Ordering::Equal => {
+                    a = a_block.parent_root;
+                    b = b_block.parent_root;
+                }
+            }
+        }
+    }
+
     fn epoch_start_slot(epoch: Epoch) -> Slot {
         misc::compute_start_slot_at_epoch::<C>(epoch)
     }
 
+    fn stored_checkpoint_state(&self, checkpoint: Checkpoint) -> Result<&BeaconState<C>> {
+        self.checkpoint_states
+            .get(&checkpoint)
+            .ok_or_else(|| Error::<C>::MissingCheckpointState { checkpoint }.into())
+    }
+
+    fn store_checkpoint_state(&mut self, checkpoint: Checkpoint) -> Result<()> {
+        if let Entry::Vacant(vacant) = self.checkpoint_states.entry(checkpoint) {
+            let base_state = &self.block_states[&checkpoint.root];
+            vacant.insert(Self::checkpoint_state(base_state, checkpoint)?);
+        }
+        Ok(())
+    }
+
+    /// Computes the state at the start of the epoch of `checkpoint` from the state produced by
+    /// processing the checkpoint block.
+    fn checkpoint_state(
+        base_state: &BeaconState<C>,
+        checkpoint: Checkpoint,
+    ) -> Result<BeaconState<C>> {
+        let mut checkpoint_state = base_state.clone();
+        process_slot::process_slots(
+            &mut checkpoint_state,
+            Self::epoch_start_slot(checkpoint.epoch),
+        )?;
+        Ok(checkpoint_state)
+    }
+
+    // `Store::on_block` reports reorgs relative to `last_head`. Updating it after inputs other
+    // than blocks keeps a head change caused by attestations from being attributed to the next
+    // block. This is served from the cache if nothing changed.
+    fn refresh_last_head(&self) -> Result<()> {
+        self.head().map(|_| ())
+    }
+
+    fn record(&mut self, event: impl FnOnce() -> StoreEvent<C>) {
+        if let Some(event_log) = &mut self.event_log {
+            event_log.events.push(event());
+        }
+    }
+
     fn delay_until_block(&mut self, block_root: H256, object: DelayedObject<C>) {
         info!("object delayed until block {:?}: {:?}", block_root, object);
         self.delayed_until_block
@@ -319,6 +1240,34 @@ impl<C: Config> Store<C> {
             .push(object)
     }
 
+    // Blocks from slots not later than the finalized block are ignored by `Store::handle_block`.
+    // Such blocks waiting for their parents would otherwise be kept forever.
+    fn drop_stale_delayed_blocks(&mut self) {
+        let finalized_slot = Self::epoch_start_slot(self.finalized_checkpoint.epoch);
+        let mut dropped = 0;
+
+        for objects in self.delayed_until_block.values_mut() {
+            let length_before = objects.len();
+            objects.retain(|object| match object {
+                DelayedObject::BeaconBlock(block) => finalized_slot < block.message.slot,
+                DelayedObject::Attestation(_) | DelayedObject::UnverifiedAttestation(_) => true,
+            });
+            dropped += length_before - objects.len();
+        }
+
+        self.delayed_until_block
+            .retain(|_, objects| !objects.is_empty());
+
+        if 0 < dropped {
+            info!(
+                "dropped {} delayed blocks made obsolete by finalization",
+                dropped
+            );
+        }
+
+        self.dropped_delayed_blocks += dropped;
+    }
+
     fn retry_delayed_until_block(&mut self, block_root: H256) -> Result<()> {
         if let Some(delayed_objects) = self.delayed_until_block.remove(&block_root) {
             self.retry_delayed(delayed_objects)?;
@@ -344,12 +1293,1145 @@ impl<C: Config> Store<C> {
         for object in objects {
             info!("retrying delayed object: {:?}", object);
             match object {
-                DelayedObject::BeaconBlock(block) => self.on_block(block)?,
-                DelayedObject::Attestation(attestation) => self.on_attestation(attestation)?,
+                DelayedObject::BeaconBlock(block) => {
+                    self.handle_block(block)?;
+                }
+                DelayedObject::Attestation(attestation) => {
+                    self.handle_attestation(attestation, true)?
+                }
+                DelayedObject::UnverifiedAttestation(attestation) => {
+                    self.handle_attestation(attestation, false)?
+                }
             }
         }
         Ok(())
     }
 }
 
-// There used to be tests here but we were forced to omit them to save time.
+#[cfg(test)]
+mod tests {
+    use ssz_types::{BitList, VariableList};
+    use transition_functions::error::TransitionError;
+    use typenum::Unsigned as _;
+    use types::{
+        config::MinimalConfig,
+        consts::FAR_FUTURE_EPOCH,
+        primitives::{AggregateSignature, PublicKey, SecretKey, Signature},
+        types::{AttestationData, BeaconBlockBody, PendingAttestation, Validator},
+    };
+
+    use super::*;
+
+    const VALIDATOR_COUNT: usize = 8;
+
+    // All validators share `secret_key`, so blocks and attestations can be signed without
+    // looking up who the proposer or the members of a committee are.
+    fn genesis_state(secret_key: &SecretKey) -> BeaconState<MinimalConfig> {
+        let balance = MinimalConfig::max_effective_balance();
+        let validator = Validator {
+            pubkey: PublicKey::from_secret_key(secret_key),
+            effective_balance: balance,
+            exit_epoch: FAR_FUTURE_EPOCH,
+            withdrawable_epoch: FAR_FUTURE_EPOCH,
+            ..Validator::default()
+        };
+        BeaconState {
+            validators: VariableList::from(vec![validator; VALIDATOR_COUNT]),
+            balances: VariableList::from(vec![balance; VALIDATOR_COUNT]),
+            // This makes the root of the latest block header equal to the root of the genesis
+            // block constructed by `Store::new`.
+            latest_block_header: BeaconBlockHeader {
+                body_root: crypto::hash_tree_root(&BeaconBlockBody::<MinimalConfig>::default()),
+                ..BeaconBlockHeader::default()
+            },
+            ..BeaconState::default()
+        }
+    }
+
+    fn new_store() -> (SecretKey, Store<MinimalConfig>) {
+        let secret_key = SecretKey::random();
+        let store = Store::new(genesis_state(&secret_key));
+        (secret_key, store)
+    }
+
+    fn genesis_root(store: &Store<MinimalConfig>) -> H256 {
+        store.finalized_checkpoint.root
+    }
+
+    // Builds a valid block on top of a block already in `store`. `graffiti` is used to tell apart
+    // blocks with the same parent and slot.
+    fn block(
+        store: &Store<MinimalConfig>,
+        secret_key: &SecretKey,
+        parent_root: H256,
+        slot: Slot,
+        graffiti: u8,
+    ) -> SignedBeaconBlock<MinimalConfig> {
+        let parent_state = &store.block_states[&parent_root];
+        let mut state = parent_state.clone();
+        process_slot::process_slots(&mut state, slot).expect("slots should be processed");
+
+        let epoch = beacon_state_accessors::get_current_epoch(&state);
+        let randao_domain =
+            beacon_state_accessors::get_domain(&state, MinimalConfig::domain_randao(), None);
+        let mut block = BeaconBlock {
+            slot,
+            parent_root,
+            body: BeaconBlockBody {
+                randao_reveal: Signature::new(
+                    crypto::hash_tree_root(&epoch).as_bytes(),
+                    randao_domain,
+                    secret_key,
+                ),
+                graffiti: [graffiti; 32],
+                ..BeaconBlockBody::default()
+            },
+            ..BeaconBlock::default()
+        };
+        block.state_root = process_slot::compute_state_root_for_block(parent_state, &block)
+            .expect("block should be valid");
+
+        let proposer_domain = beacon_state_accessors::get_domain(
+            &state,
+            MinimalConfig::domain_beacon_proposer(),
+            None,
+        );
+        SignedBeaconBlock {
+            signature: Signature::new(
+                crypto::hash_tree_root(&block).as_bytes(),
+                proposer_domain,
+                secret_key,
+            ),
+            message: block,
+        }
+    }
+
+    // Advances `store` to `slot` if needed and passes it a block built by `block`.
+    fn add_block(
+        store: &mut Store<MinimalConfig>,
+        secret_key: &SecretKey,
+        parent_root: H256,
+        slot: Slot,
+        graffiti: u8,
+    ) -> H256 {
+        if store.slot < slot {
+            store.on_slot(slot).expect("slot should be accepted");
+        }
+        let signed_block = block(store, secret_key, parent_root, slot, graffiti);
+        let root = crypto::hash_tree_root(&signed_block.message);
+        store
+            .on_block(signed_block)
+            .expect("block should be accepted");
+        root
+    }
+
+    // Builds an attestation signed by the whole committee of `slot` with the genesis checkpoint as
+    // its target.
+    fn attestation(
+        store: &Store<MinimalConfig>,
+        secret_key: &SecretKey,
+        slot: Slot,
+        beacon_block_root: H256,
+    ) -> Attestation<MinimalConfig> {
+        let target = Checkpoint {
+            epoch: MinimalConfig::genesis_epoch(),
+            root: genesis_root(store),
+        };
+        let target_state = &store.checkpoint_states[&target];
+        let data = AttestationData {
+            slot,
+            index: 0,
+            beacon_block_root,
+            source: target,
+            target,
+        };
+
+        let committee = beacon_state_accessors::get_beacon_committee(target_state, slot, 0)
+            .expect("committee should be computed");
+        let domain = beacon_state_accessors::get_domain(
+            target_state,
+            MinimalConfig::domain_attestation(),
+            Some(target.epoch),
+        );
+        let signature =
+            Signature::new(crypto::hash_tree_root(&data).as_bytes(), domain, secret_key);
+
+        let mut aggregation_bits =
+            BitList::with_capacity(committee.len()).expect("committee should fit in a BitList");
+        let mut aggregate_signature = AggregateSignature::new();
+        for position in 0..committee.len() {
+            aggregation_bits
+                .set(position, true)
+                .expect("position should be within the committee");
+            aggregate_signature.add(&signature);
+        }
+
+        Attestation {
+            aggregation_bits,
+            data,
+            signature: aggregate_signature,
+        }
+    }
+
+    // Latest messages in which the validator at each index votes for the root at that index.
+    fn votes(roots: &[H256]) -> HashMap<ValidatorIndex, Checkpoint> {
+        roots
+            .iter()
+            .enumerate()
+            .map(|(index, &root)| {
+                let message = Checkpoint {
+                    epoch: MinimalConfig::genesis_epoch(),
+                    root,
+                };
+                (index as ValidatorIndex, message)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn replay_reproduces_session_with_rejected_block() {
+        let secret_key = SecretKey::random();
+        let mut store = Store::with_event_log(genesis_state(&secret_key));
+        let genesis_root = genesis_root(&store);
+
+        let block_1 = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        store.on_slot(2).expect("slot should be accepted");
+        let mut rejected_block = block(&store, &secret_key, block_1, 2, 0);
+        // Changing the block after signing it invalidates the signature.
+        rejected_block.message.body.graffiti = [1; 32];
+        store
+            .on_block(rejected_block)
+            .expect_err("block with an invalid signature should be rejected");
+        let rejected_event_count = store
+            .event_log()
+            .expect("events should be recorded")
+            .events
+            .len();
+        add_block(&mut store, &secret_key, block_1, 2, 2);
+        store
+            .on_attestation(attestation(&store, &secret_key, 1, block_1))
+            .expect("attestation should be accepted");
+
+        let event_log = store.event_log().expect("events should be recorded");
+        let decoded_log = StoreEventLog::from_ssz_bytes(&event_log.as_ssz_bytes())
+            .expect("event log should be decoded");
+        let (replayed, last_result) = Store::replay(&decoded_log);
+
+        last_result.expect("last event should be accepted");
+        assert_eq!(
+            replayed.head().expect("head should be computed"),
+            store.head().expect("head should be computed"),
+        );
+        assert_eq!(replayed.finalized_checkpoint, store.finalized_checkpoint);
+        assert_eq!(
+            replayed.event_log().map(|log| log.events.len()),
+            Some(event_log.events.len()),
+        );
+
+        let mut truncated_log = event_log.clone();
+        truncated_log.events.truncate(rejected_event_count);
+        let (_, last_result) = Store::replay(&truncated_log);
+        let error = last_result.expect_err("last event should be rejected again");
+        assert_eq!(
+            error.downcast_ref::<TransitionError>(),
+            Some(&TransitionError::InvalidProposerSignature),
+        );
+    }
+
+    #[test]
+    fn attestation_invalidates_cached_head() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_a = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_b = add_block(&mut store, &secret_key, genesis_root, 1, 1);
+        let lower_root = block_a.min(block_b);
+        let higher_root = block_a.max(block_b);
+
+        // Ties are broken in favor of the block with the higher root.
+        assert_eq!(store.head().expect("head should be computed"), higher_root);
+        assert_eq!(store.cached_head.get(), Some(higher_root));
+
+        store.on_slot(2).expect("slot should be accepted");
+        store
+            .on_attestation(attestation(&store, &secret_key, 1, lower_root))
+            .expect("attestation should be accepted");
+
+        // A stale cache would still return `higher_root`.
+        assert_eq!(store.head().expect("head should be computed"), lower_root);
+    }
+
+    #[test]
+    fn persisted_store_survives_ssz_round_trip() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_1 = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_2a = add_block(&mut store, &secret_key, block_1, 2, 0);
+        let block_2b = add_block(&mut store, &secret_key, block_1, 2, 1);
+        // Without the latest message the block with the higher root would be the head.
+        let voted_root = block_2a.min(block_2b);
+        store.on_slot(3).expect("slot should be accepted");
+        store
+            .on_attestation(attestation(&store, &secret_key, 2, voted_root))
+            .expect("attestation should be accepted");
+
+        let bytes = store.to_persisted().as_ssz_bytes();
+        let persisted = PersistedStore::from_ssz_bytes(&bytes).expect("store should be decoded");
+        let restored = Store::from_persisted(persisted).expect("store should be restored");
+
+        assert_eq!(
+            restored.head().expect("head should be computed"),
+            voted_root
+        );
+        assert_eq!(restored.slot, store.slot);
+        assert_eq!(restored.justified_checkpoint, store.justified_checkpoint);
+        assert_eq!(restored.finalized_checkpoint, store.finalized_checkpoint);
+        assert_eq!(restored.latest_messages, store.latest_messages);
+        assert_eq!(restored.blocks.len(), store.blocks.len());
+    }
+
+    #[test]
+    fn finalized_checkpoint_state_is_at_epoch_start() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        // The checkpoint block is from the middle of the epoch before the checkpoint epoch.
+        let root = add_block(&mut store, &secret_key, genesis_root, 9, 0);
+        let checkpoint = Checkpoint { epoch: 2, root };
+
+        store
+            .update_checkpoints(checkpoint, checkpoint)
+            .expect("checkpoints should be updated");
+
+        let finalized_state = store
+            .finalized_checkpoint_state()
+            .expect("finalized checkpoint state should be stored");
+        assert_eq!(
+            finalized_state.slot,
+            misc::compute_start_slot_at_epoch::<MinimalConfig>(checkpoint.epoch),
+        );
+        assert_eq!(store.block_states[&root].slot, 9);
+    }
+
+    #[test]
+    fn on_attestation_rejects_invalid_signature() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let root = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        store.on_slot(3).expect("slot should be accepted");
+        store
+            .on_attestation(attestation(&store, &secret_key, 1, root))
+            .expect("attestation should be accepted");
+        let latest_messages = store.latest_messages.clone();
+        let signed_by_another_key = attestation(&store, &SecretKey::random(), 2, root);
+
+        let error = store
+            .on_attestation(signed_by_another_key.clone())
+            .expect_err("attestation should be rejected");
+
+        match error.downcast_ref() {
+            Some(Error::<MinimalConfig>::InvalidAttestationSignature { attestation }) => {
+                assert_eq!(*attestation, signed_by_another_key)
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert_eq!(store.latest_messages, latest_messages);
+    }
+
+    #[test]
+    fn blocks_at_slot_returns_both_sides_of_fork() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_1 = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_2a = add_block(&mut store, &secret_key, block_1, 2, 0);
+        let block_2b = add_block(&mut store, &secret_key, block_1, 2, 1);
+        add_block(&mut store, &secret_key, block_2a, 3, 0);
+
+        let mut expected = vec![block_2a, block_2b];
+        expected.sort();
+        let roots = store
+            .blocks_at_slot(2)
+            .into_iter()
+            .map(|(root, signed_block)| {
+                assert_eq!(signed_block.message.slot, 2);
+                root
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(roots, expected);
+        assert!(store.blocks_at_slot(4).is_empty());
+    }
+
+    #[test]
+    fn block_weights_order_forks_by_votes() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_a = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_b = add_block(&mut store, &secret_key, genesis_root, 1, 1);
+        let block_b_child = add_block(&mut store, &secret_key, block_b, 2, 0);
+        store.set_latest_messages(votes(&[block_a, block_a, block_a, block_b_child]));
+
+        let weights = store.block_weights().expect("weights should be computed");
+        let balance = MinimalConfig::max_effective_balance();
+
+        assert_eq!(weights.len(), 4);
+        assert_eq!(weights[&genesis_root], 4 * balance);
+        assert_eq!(weights[&block_a], 3 * balance);
+        assert_eq!(weights[&block_b], balance);
+        assert_eq!(weights[&block_b_child], balance);
+        assert!(weights[&block_b] < weights[&block_a]);
+        assert_eq!(store.head().expect("head should be computed"), block_a);
+    }
+
+    #[test]
+    fn head_proof_attestations_give_head_plurality() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_a = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_b = add_block(&mut store, &secret_key, genesis_root, 1, 1);
+        store.on_slot(4).expect("slot should be accepted");
+        for (slot, root) in [(1, block_a), (2, block_a), (3, block_b)].iter().copied() {
+            store
+                .on_attestation(attestation(&store, &secret_key, slot, root))
+                .expect("attestation should be accepted");
+        }
+
+        let (header, attestations) = store.head_proof().expect("proof should be constructed");
+
+        assert_eq!(crypto::hash_tree_root(&header), block_a);
+        assert!(attestations
+            .iter()
+            .all(|attestation| attestation.data.beacon_block_root == block_a));
+        let tallied_balance = attestations
+            .iter()
+            .map(|attestation| attestation.aggregation_bits.num_set_bits() as Gwei)
+            .sum::<Gwei>()
+            * MinimalConfig::max_effective_balance();
+        let sibling_weight = store.block_weights().expect("weights should be computed")[&block_b];
+        assert_eq!(tallied_balance, 2 * MinimalConfig::max_effective_balance());
+        assert!(sibling_weight < tallied_balance);
+    }
+
+    #[test]
+    fn on_block_reports_reorg_depth() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let first = block(&store, &secret_key, genesis_root, 1, 0);
+        let second = block(&store, &secret_key, genesis_root, 1, 1);
+        // Ties are broken in favor of the block with the higher root, so the branch built on the
+        // other one loses as soon as that block arrives.
+        let (losing_block, winning_block) =
+            if crypto::hash_tree_root(&first.message) < crypto::hash_tree_root(&second.message) {
+                (first, second)
+            } else {
+                (second, first)
+            };
+        let losing_root = crypto::hash_tree_root(&losing_block.message);
+        let winning_root = crypto::hash_tree_root(&winning_block.message);
+
+        store.on_slot(2).expect("slot should be accepted");
+        assert_eq!(
+            store
+                .on_block(losing_block)
+                .expect("block should be accepted"),
+            None
+        );
+        let old_head = add_block(&mut store, &secret_key, losing_root, 2, 0);
+        assert_eq!(store.head().expect("head should be computed"), old_head);
+
+        let reorg = store
+            .on_block(winning_block)
+            .expect("block should be accepted");
+
+        assert_eq!(
+            reorg,
+            Some(ReorgEvent {
+                old_head,
+                new_head: winning_root,
+                common_ancestor_slot: 0,
+            }),
+        );
+    }
+
+    #[test]
+    fn on_block_does_not_compute_head_for_delayed_block() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let future_block = block(&store, &secret_key, genesis_root, 1, 0);
+        store.set_latest_messages(HashMap::new());
+
+        assert_eq!(
+            store
+                .on_block(future_block)
+                .expect("block should be delayed"),
+            None
+        );
+        assert_eq!(store.cached_head.get(), None);
+        assert_eq!(store.delayed_until_slot.len(), 1);
+    }
+
+    #[test]
+    fn on_block_compares_against_head_after_attestations() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_a = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_b = add_block(&mut store, &secret_key, genesis_root, 1, 1);
+        let lower_root = block_a.min(block_b);
+        assert_eq!(
+            store.head().expect("head should be computed"),
+            block_a.max(block_b),
+        );
+        store.on_slot(2).expect("slot should be accepted");
+        store
+            .on_attestation(attestation(&store, &secret_key, 1, lower_root))
+            .expect("attestation should be accepted");
+
+        let child = block(&store, &secret_key, lower_root, 2, 0);
+
+        // The attestation already moved the head to `lower_root`, so this is not a reorg.
+        assert_eq!(
+            store.on_block(child).expect("block should be accepted"),
+            None
+        );
+    }
+
+    #[test]
+    fn encoded_canonical_blocks_decode_to_stored_blocks() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_1 = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_3 = add_block(&mut store, &secret_key, block_1, 3, 0);
+        let block_4 = add_block(&mut store, &secret_key, block_3, 4, 0);
+        add_block(&mut store, &secret_key, block_4, 5, 0);
+        store.set_latest_messages(votes(&[block_4]));
+        // A block on another branch that must not be included.
+        add_block(&mut store, &secret_key, block_1, 4, 1);
+
+        let roots = store
+            .encode_canonical_blocks(1, 5)
+            .expect("blocks should be encoded")
+            .into_iter()
+            .map(|bytes| {
+                let decoded = SignedBeaconBlock::<MinimalConfig>::from_ssz_bytes(&bytes)
+                    .expect("block should be decoded");
+                let root = crypto::hash_tree_root(&decoded.message);
+                assert_eq!(store.block(root), Some(&decoded));
+                root
+            })
+            .collect::<Vec<_>>();
+
+        // Slot 2 is empty and slot 5 is outside the range.
+        assert_eq!(roots, vec![block_1, block_3, block_4]);
+    }
+
+    #[test]
+    fn set_latest_messages_moves_head_and_drops_unknown_blocks() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_a = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_b = add_block(&mut store, &secret_key, genesis_root, 1, 1);
+        let lower_root = block_a.min(block_b);
+        let unknown_root = H256::repeat_byte(0xff);
+
+        store.set_latest_messages(votes(&[lower_root, lower_root, unknown_root]));
+
+        assert_eq!(store.head().expect("head should be computed"), lower_root);
+        assert_eq!(store.latest_messages.len(), 2);
+        assert!(!store.latest_messages.contains_key(&2));
+    }
+
+    #[test]
+    fn finalization_drops_delayed_blocks_that_can_never_be_processed() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let orphan = |slot| SignedBeaconBlock {
+            message: BeaconBlock {
+                slot,
+                parent_root: H256::repeat_byte(1),
+                ..BeaconBlock::default()
+            },
+            ..SignedBeaconBlock::default()
+        };
+        let root = add_block(&mut store, &secret_key, genesis_root, 9, 0);
+        store.on_block(orphan(3)).expect("block should be delayed");
+        store.on_block(orphan(17)).expect("block should be delayed");
+        assert_eq!(store.delayed_until_block[&H256::repeat_byte(1)].len(), 2);
+
+        let checkpoint = Checkpoint { epoch: 2, root };
+        store
+            .update_checkpoints(checkpoint, checkpoint)
+            .expect("checkpoints should be updated");
+
+        // The block from slot 3 would be ignored even if its parent arrived.
+        assert_eq!(store.dropped_delayed_blocks(), 1);
+        match store.delayed_until_block[&H256::repeat_byte(1)].as_slice() {
+            [DelayedObject::BeaconBlock(block)] => assert_eq!(block.message.slot, 17),
+            other => panic!("unexpected delayed objects: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn justification_progress_counts_target_attestations() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let head_root = add_block(&mut store, &secret_key, genesis_root, 9, 0);
+
+        let head_state = store
+            .block_states
+            .get_mut(&head_root)
+            .expect("head state should be stored");
+        let target_root = beacon_state_accessors::get_block_root(head_state, 1)
+            .expect("target root should be available");
+        // 3 of the 8 validators vote for the correct target. One more votes for another root.
+        for (slot, root) in [
+            (8, target_root),
+            (9, target_root),
+            (10, target_root),
+            (11, H256::repeat_byte(1)),
+        ]
+        .iter()
+        .copied()
+        {
+            let committee = beacon_state_accessors::get_beacon_committee(head_state, slot, 0)
+                .expect("committee should be computed");
+            let mut aggregation_bits =
+                BitList::with_capacity(committee.len()).expect("committee should fit in a BitList");
+            for position in 0..committee.len() {
+                aggregation_bits
+                    .set(position, true)
+                    .expect("position should be within the committee");
+            }
+            let pending_attestation = PendingAttestation {
+                aggregation_bits,
+                data: AttestationData {
+                    slot,
+                    target: Checkpoint { epoch: 1, root },
+                    ..AttestationData::default()
+                },
+                ..PendingAttestation::default()
+            };
+            head_state
+                .current_epoch_attestations
+                .push(pending_attestation)
+                .expect("attestation should fit in the state");
+        }
+
+        let balance = MinimalConfig::max_effective_balance();
+        assert_eq!(
+            store
+                .justification_progress()
+                .expect("progress should be computed"),
+            (3 * balance, VALIDATOR_COUNT as Gwei * balance),
+        );
+    }
+
+    #[test]
+    fn orphaned_blocks_returns_only_abandoned_fork() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let main_1 = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let main_2 = add_block(&mut store, &secret_key, main_1, 2, 0);
+        let main_3 = add_block(&mut store, &secret_key, main_2, 3, 0);
+        let fork_2 = add_block(&mut store, &secret_key, main_1, 2, 1);
+        let fork_3 = add_block(&mut store, &secret_key, fork_2, 3, 1);
+        store.set_latest_messages(votes(&[main_3, main_3, fork_3]));
+
+        assert_eq!(store.head().expect("head should be computed"), main_3);
+        assert_eq!(
+            store
+                .orphaned_blocks()
+                .expect("orphaned blocks should be found"),
+            vec![fork_2, fork_3],
+        );
+    }
+
+    #[test]
+    fn checkpoint_states_survive_ssz_round_trip_in_canonical_order() {
+        let early = Checkpoint {
+            epoch: 1,
+            root: H256::repeat_byte(2),
+        };
+        let late = Checkpoint {
+            epoch: 2,
+            root: H256::repeat_byte(1),
+        };
+        let state = |slot| BeaconState::<MinimalConfig> {
+            slot,
+            ..BeaconState::default()
+        };
+        let map = hashmap! {late => state(16), early => state(8)};
+
+        let checkpoint_states = CheckpointStates::from(map.clone());
+        let decoded = CheckpointStates::from_ssz_bytes(&checkpoint_states.as_ssz_bytes())
+            .expect("checkpoint states should be decoded");
+
+        assert_eq!(
+            decoded
+                .states
+                .iter()
+                .map(|checkpoint_state| checkpoint_state.checkpoint)
+                .collect::<Vec<_>>(),
+            vec![early, late],
+        );
+        assert_eq!(HashMap::from(decoded), map);
+    }
+
+    #[test]
+    fn unverified_attestations_affect_head_only_after_verification() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_a = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_b = add_block(&mut store, &secret_key, genesis_root, 1, 1);
+        let lower_root = block_a.min(block_b);
+        let higher_root = block_a.max(block_b);
+        store.on_slot(3).expect("slot should be accepted");
+        let valid = attestation(&store, &secret_key, 1, lower_root);
+        let invalid = attestation(&store, &SecretKey::random(), 2, lower_root);
+
+        store
+            .on_unverified_attestation(valid)
+            .expect("attestation should be accepted");
+        store
+            .on_unverified_attestation(invalid.clone())
+            .expect("attestation should be accepted");
+
+        assert!(store.latest_messages.is_empty());
+        assert_eq!(store.head().expect("head should be computed"), higher_root);
+
+        let rejected = store
+            .verify_pending_attestations()
+            .expect("attestations should be verified");
+
+        assert_eq!(rejected, vec![invalid]);
+        assert_eq!(store.latest_messages.len(), 1);
+        assert_eq!(store.head().expect("head should be computed"), lower_root);
+    }
+
+    #[test]
+    fn debug_dump_describes_forked_store() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_a = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_b = add_block(&mut store, &secret_key, genesis_root, 1, 1);
+        let block_a_child = add_block(&mut store, &secret_key, block_a, 2, 0);
+        store.set_latest_messages(votes(&[block_a_child, block_a_child, block_b]));
+
+        let dump = store.debug_dump().expect("dump should be created");
+
+        assert_eq!(dump.slot, 2);
+        assert_eq!(dump.head, block_a_child);
+        assert_eq!(dump.edges.len(), store.blocks.len());
+        assert_eq!(dump.edges[0], (genesis_root, H256::zero()));
+        assert!(dump.edges.contains(&(block_a, genesis_root)));
+        assert!(dump.edges.contains(&(block_b, genesis_root)));
+        assert!(dump.edges.contains(&(block_a_child, block_a)));
+        assert_eq!(
+            dump.weights,
+            store
+                .block_weights()
+                .expect("weights should be computed")
+                .into_iter()
+                .collect(),
+        );
+        assert_eq!(dump.latest_message_counts[&block_a_child], 2);
+        assert_eq!(dump.latest_message_counts[&block_b], 1);
+
+        let yaml = serde_yaml::to_string(&dump).expect("dump should be serialized");
+        let decoded: ForkChoiceDump =
+            serde_yaml::from_str(&yaml).expect("serialized dump should be valid");
+
+        assert_eq!(decoded.edges, dump.edges);
+        assert_eq!(decoded.weights, dump.weights);
+    }
+
+    #[test]
+    fn process_block_and_head_advances_head_along_chain() {
+        let (secret_key, mut store) = new_store();
+        let mut parent_root = genesis_root(&store);
+
+        for slot in 1..=4 {
+            store.on_slot(slot).expect("slot should be accepted");
+            let signed_block = block(&store, &secret_key, parent_root, slot, 0);
+            let root = crypto::hash_tree_root(&signed_block.message);
+
+            let head = store
+                .process_block_and_head(signed_block)
+                .expect("block should be accepted");
+
+            assert_eq!(head, root);
+            parent_root = root;
+        }
+
+        // A block whose parent is unknown is delayed and leaves the head unchanged.
+        let orphan = SignedBeaconBlock {
+            message: BeaconBlock {
+                slot: 4,
+                parent_root: H256::repeat_byte(1),
+                ..BeaconBlock::default()
+            },
+            ..SignedBeaconBlock::default()
+        };
+
+        assert_eq!(
+            store
+                .process_block_and_head(orphan)
+                .expect("block should be delayed"),
+            parent_root,
+        );
+    }
+
+    #[test]
+    fn reimporting_known_blocks_skips_state_transition() {
+        let (secret_key, mut store) = new_store();
+        let mut parent_root = genesis_root(&store);
+        let mut signed_blocks = vec![];
+
+        for slot in 1..=100 {
+            store.on_slot(slot).expect("slot should be accepted");
+            let signed_block = block(&store, &secret_key, parent_root, slot, 0);
+            parent_root = crypto::hash_tree_root(&signed_block.message);
+            store
+                .on_block(signed_block.clone())
+                .expect("block should be accepted");
+            signed_blocks.push(signed_block);
+        }
+
+        // Running the state transition from any of these states would fail with `SlotInPast`.
+        // Importing the blocks again succeeds only because none of the 100 parent states is cloned
+        // and transitioned, whereas previously every one of them was.
+        for state in store.block_states.values_mut() {
+            state.slot = Slot::max_value();
+        }
+
+        for signed_block in signed_blocks {
+            assert_eq!(
+                store
+                    .on_block(signed_block)
+                    .expect("known block should be accepted"),
+                None,
+            );
+        }
+        assert_eq!(store.blocks.len(), 101);
+    }
+
+    #[test]
+    fn conflicting_same_epoch_attestation_is_recorded_and_ignored() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_a = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_b = add_block(&mut store, &secret_key, genesis_root, 1, 1);
+        store.on_slot(2).expect("slot should be accepted");
+
+        store
+            .on_attestation(attestation(&store, &secret_key, 1, block_a))
+            .expect("attestation should be accepted");
+        let latest_messages = store.latest_messages.clone();
+        let conflicting = attestation(&store, &secret_key, 1, block_b);
+        store
+            .on_attestation(conflicting.clone())
+            .expect("attestation should be accepted");
+        // Receiving the same conflicting attestation again does not record it twice.
+        store
+            .on_attestation(conflicting)
+            .expect("attestation should be accepted");
+
+        let (&validator_index, &latest_message) = latest_messages
+            .iter()
+            .next()
+            .expect("committee should not be empty");
+        assert_eq!(store.latest_messages, latest_messages);
+        assert_eq!(
+            store.conflicting_messages(),
+            &[ConflictingMessage {
+                validator_index,
+                latest_message,
+                conflicting_message: LatestMessage {
+                    epoch: MinimalConfig::genesis_epoch(),
+                    root: block_b,
+                },
+            }][..],
+        );
+    }
+
+    #[test]
+    fn viable_tree_snapshot_lists_fork_with_parent_links() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_a = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_b = add_block(&mut store, &secret_key, genesis_root, 1, 1);
+        let block_a_child = add_block(&mut store, &secret_key, block_a, 2, 0);
+        store.set_latest_messages(votes(&[block_a_child, block_b]));
+
+        let balance = MinimalConfig::max_effective_balance();
+        let node = |root, parent_root, slot, weight| BlockNode {
+            root,
+            parent_root,
+            slot,
+            weight,
+        };
+        let mut slot_1_nodes = vec![
+            node(block_a, genesis_root, 1, balance),
+            node(block_b, genesis_root, 1, balance),
+        ];
+        slot_1_nodes.sort_by_key(|node| node.root);
+        let mut expected_nodes = vec![node(genesis_root, H256::zero(), 0, 2 * balance)];
+        expected_nodes.extend(slot_1_nodes);
+        expected_nodes.push(node(block_a_child, block_a, 2, balance));
+
+        assert_eq!(
+            store
+                .viable_tree_snapshot()
+                .expect("snapshot should be computed"),
+            expected_nodes,
+        );
+    }
+
+    #[test]
+    fn block_with_missing_grandparent_is_delayed_until_it_arrives() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        store.on_slot(1).expect("slot should be accepted");
+        let grandparent = block(&store, &secret_key, genesis_root, 1, 0);
+        let grandparent_root = crypto::hash_tree_root(&grandparent.message);
+        store
+            .on_block(grandparent.clone())
+            .expect("block should be accepted");
+        let parent_root = add_block(&mut store, &secret_key, grandparent_root, 2, 0);
+        store.on_slot(3).expect("slot should be accepted");
+        let child = block(&store, &secret_key, parent_root, 3, 0);
+        let child_root = crypto::hash_tree_root(&child.message);
+
+        // The parent and its state are known, but walking to the finalized block hits a gap.
+        store.blocks.remove(&grandparent_root);
+
+        assert_eq!(
+            store.on_block(child).expect("block should be delayed"),
+            None
+        );
+        assert!(!store.blocks.contains_key(&child_root));
+
+        store
+            .on_block(grandparent)
+            .expect("block should be accepted");
+
+        assert!(store.blocks.contains_key(&child_root));
+    }
+
+    #[test]
+    fn head_state_at_slot_processes_empty_slots_after_head() {
+        let (secret_key, mut store) = new_store();
+        let head = add_block(&mut store, &secret_key, genesis_root(&store), 2, 0);
+        // Several epochs pass without blocks.
+        let slot = 3 * <MinimalConfig as Config>::SlotsPerEpoch::U64 + 1;
+        store.on_slot(slot).expect("slot should be accepted");
+
+        let state = store
+            .head_state_at_slot(slot)
+            .expect("slots should be processed");
+
+        assert_eq!(state.slot, slot);
+        assert_eq!(state.latest_block_header.slot, 2);
+        assert_eq!(store.head().expect("head should be computed"), head);
+        assert_eq!(
+            store
+                .head_state()
+                .expect("head state should be stored")
+                .slot,
+            2
+        );
+
+        let error = store
+            .head_state_at_slot(1)
+            .expect_err("slot before head state should be rejected");
+        assert_eq!(
+            error.downcast_ref::<TransitionError>(),
+            Some(&TransitionError::SlotInPast {
+                state_slot: 2,
+                slot: 1,
+            }),
+        );
+    }
+
+    #[test]
+    fn head_support_is_latest_attesting_balance_of_head() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        let block_a = add_block(&mut store, &secret_key, genesis_root, 1, 0);
+        let block_b = add_block(&mut store, &secret_key, genesis_root, 1, 1);
+        let block_a_child = add_block(&mut store, &secret_key, block_a, 2, 0);
+        store.set_latest_messages(votes(&[block_a, block_a_child, block_a_child, block_b]));
+
+        let head = store.head().expect("head should be computed");
+        let support = store.head_support().expect("support should be computed");
+
+        // Only the votes for the head block itself count. The vote for its parent does not.
+        assert_eq!(head, block_a_child);
+        assert_eq!(support, 2 * MinimalConfig::max_effective_balance());
+        assert_eq!(
+            support,
+            store
+                .latest_attesting_balance(head, &store.blocks[&head].message)
+                .expect("balance should be computed"),
+        );
+    }
+
+    #[test]
+    fn on_attestation_accepts_genesis_epoch_target_at_genesis_epoch() {
+        let (secret_key, mut store) = new_store();
+        let genesis_root = genesis_root(&store);
+        store.on_slot(1).expect("slot should be accepted");
+        let attestation = attestation(&store, &secret_key, 0, genesis_root);
+
+        // `GENESIS_EPOCH` is 0, so a target epoch before it cannot be represented at all.
+        assert_eq!(
+            misc::compute_epoch_at_slot::<MinimalConfig>(store.slot),
+            MinimalConfig::genesis_epoch(),
+        );
+        assert_eq!(
+            attestation.data.target.epoch,
+            MinimalConfig::genesis_epoch()
+        );
+
+        store
+            .on_attestation(attestation.clone())
+            .expect("attestation should be accepted");
+
+        let expected_message = LatestMessage {
+            epoch: MinimalConfig::genesis_epoch(),
+            root: genesis_root,
+        };
+        assert_eq!(store.latest_messages.len(), 1);
+        assert!(store
+            .latest_messages
+            .values()
+            .all(|message| *message == expected_message));
+        assert_eq!(
+            store.attestations[&MinimalConfig::genesis_epoch()],
+            vec![attestation]
+        );
+    }
+
+    #[test]
+    fn attestation_is_includable_after_inclusion_delay() {
+        let (secret_key, mut store) = new_store();
+        let attestation_slot = 1;
+        let attestation = attestation(&store, &secret_key, attestation_slot, genesis_root(&store));
+        let earliest_slot = attestation_slot + MinimalConfig::min_attestation_inclusion_delay();
+        let last_slot = attestation_slot + <MinimalConfig as Config>::SlotsPerEpoch::U64;
+
+        assert_eq!(store.earliest_inclusion_slot(&attestation), earliest_slot);
+
+        store
+            .on_slot(earliest_slot - 1)
+            .expect("slot should be accepted");
+        assert!(!store.is_includable_now(&attestation));
+
+        store
+            .on_slot(earliest_slot)
+            .expect("slot should be accepted");
+        assert!(store.is_includable_now(&attestation));
+
+        store.on_slot(last_slot).expect("slot should be accepted");
+        assert!(store.is_includable_now(&attestation));
+
+        store
+            .on_slot(last_slot + 1)
+            .expect("slot should be accepted");
+        assert!(!store.is_includable_now(&attestation));
+    }
+
+    #[test]
+    fn justified_checkpoint_state_is_stored_before_finalization() {
+        let mut store = Store::<MinimalConfig>::new(BeaconState::default());
+        let genesis_checkpoint = store.finalized_checkpoint;
+
+        let block = BeaconBlock {
+            slot: misc::compute_start_slot_at_epoch::<MinimalConfig>(1),
+            parent_root: genesis_checkpoint.root,
+            ..BeaconBlock::default()
+        };
+        let root = crypto::hash_tree_root(&block);
+        let justified_checkpoint = Checkpoint { epoch: 1, root };
+        let state = BeaconState {
+            slot: block.slot,
+            current_justified_checkpoint: justified_checkpoint,
+            finalized_checkpoint: genesis_checkpoint,
+            ..BeaconState::default()
+        };
+        store.blocks.insert(
+            root,
+            SignedBeaconBlock {
+                message: block,
+                ..SignedBeaconBlock::default()
+            },
+        );
+        store.block_states.insert(root, state);
+
+        store
+            .update_checkpoints(justified_checkpoint, genesis_checkpoint)
+            .expect("checkpoints should be updated");
+
+        assert_eq!(store.justified_checkpoint, justified_checkpoint);
+        assert_eq!(store.finalized_checkpoint, genesis_checkpoint);
+        assert!(store.checkpoint_states.contains_key(&justified_checkpoint));
+        assert_eq!(store.head().expect("head should be computed"), root);
+        assert_eq!(store.head_support().expect("head should be computed"), 0);
+    }
+
+    #[test]
+    fn on_slot_rejects_slot_that_is_not_later() {
+        let mut store = Store::<MinimalConfig>::new(BeaconState::default());
+
+        let error = store.on_slot(0).expect_err("slot 0 should be rejected");
+
+        match error.downcast_ref() {
+            Some(Error::<MinimalConfig>::SlotNotLater {
+                old_slot: 0,
+                new_slot: 0,
+            }) => {}
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn on_block_rejects_block_too_far_in_future() {
+        let mut store = Store::<MinimalConfig>::new(BeaconState::default());
+        let block_slot = DEFAULT_MAX_FUTURE_SLOTS + 1;
+        let signed_block = SignedBeaconBlock {
+            message: BeaconBlock {
+                slot: block_slot,
+                ..BeaconBlock::default()
+            },
+            ..SignedBeaconBlock::default()
+        };
+
+        let error = store
+            .on_block(signed_block)
+            .expect_err("block should be rejected");
+
+        match error.downcast_ref() {
+            Some(Error::<MinimalConfig>::BlockTooFarInFuture {
+                slot: 0,
+                block_slot: actual_block_slot,
+                max_future_slots: DEFAULT_MAX_FUTURE_SLOTS,
+            }) => assert_eq!(*actual_block_slot, block_slot),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_finalized_checkpoint_state_is_reported() {
+        let mut store = Store::<MinimalConfig>::new(BeaconState::default());
+        let finalized_checkpoint = store.finalized_checkpoint;
+        store.checkpoint_states.clear();
+
+        let error = store
+            .finalized_checkpoint_state()
+            .expect_err("state should be missing");
+
+        match error.downcast_ref() {
+            Some(Error::<MinimalConfig>::MissingCheckpointState { checkpoint }) => {
+                assert_eq!(*checkpoint, finalized_checkpoint)
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
@@ -42,7 +42,7 @@ fn run_node<C: Config + DeserializeOwned>(config: RuntimeConfig) -> Result<()> {
 
     let node = Node::new(genesis_state);
 
-    let tick_stream = slot_timer::start::<C>(node.head_state().genesis_time)?;
+    let tick_stream = slot_timer::start::<C>(node.head_state()?.genesis_time)?;
 
     // In previous versions, `Node` would consume an `Iterator` of inputs and produce an `Iterator`
     // of outputs. This approach required no explicit synchronization, but made abstracting over
@@ -11,7 +11,7 @@ use types::{
     beacon_state::BeaconState,
     config::Config,
     primitives::{Slot, H256},
-    types::{Attestation, BeaconBlock, Checkpoint},
+    types::{Attestation, Checkpoint, SignedBeaconBlock},
 };
 
 pub struct Node<C: Config>(Store<C>);
@@ -21,7 +21,7 @@ impl<C: Config> Node<C> {
         Self(Store::new(beacon_state))
     }
 
-    pub fn head_state(&self) -> &BeaconState<C> {
+    pub fn head_state(&self) -> Result<&BeaconState<C>> {
         self.0.head_state()
     }
 
@@ -36,9 +36,12 @@ impl<C: Config> Node<C> {
 }
 
 impl<C: Config> Networked<C> for Node<C> {
-    fn accept_beacon_block(&mut self, block: BeaconBlock<C>) -> Result<()> {
+    fn accept_beacon_block(&mut self, block: SignedBeaconBlock<C>) -> Result<()> {
         info!("received beacon block: {:?}", block);
-        self.0.on_block(block)
+        if let Some(reorg) = self.0.on_block(block)? {
+            info!("head changed in a reorg: {:?}", reorg);
+        }
+        Ok(())
     }
 
     fn accept_beacon_attestation(&mut self, attestation: Attestation<C>) -> Result<()> {
@@ -46,19 +49,19 @@ impl<C: Config> Networked<C> for Node<C> {
         self.0.on_attestation(attestation)
     }
 
-    fn get_status(&self) -> Status {
-        let head_state = self.0.head_state();
+    fn get_status(&self) -> Result<Status> {
+        let head_state = self.0.head_state()?;
         let Checkpoint { epoch, root } = head_state.finalized_checkpoint;
-        Status {
+        Ok(Status {
             fork_version: head_state.fork.current_version,
             finalized_root: root,
             finalized_epoch: epoch,
             head_root: crypto::hash_tree_root(head_state),
             head_slot: head_state.slot,
-        }
+        })
     }
 
-    fn get_beacon_block(&self, root: H256) -> Option<&BeaconBlock<C>> {
+    fn get_beacon_block(&self, root: H256) -> Option<&SignedBeaconBlock<C>> {
         self.0.block(root)
     }
 }
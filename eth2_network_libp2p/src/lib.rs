@@ -27,7 +27,7 @@ use thiserror::Error;
 use types::{
     config::Config,
     primitives::Version,
-    types::{Attestation, BeaconBlock},
+    types::{Attestation, SignedBeaconBlock},
 };
 
 pub use eth2_libp2p::NetworkConfig;
@@ -95,7 +95,7 @@ enum EventHandlerError {
 
 #[allow(clippy::large_enum_variant)]
 enum Gossip<C: Config> {
-    BeaconBlock(BeaconBlock<C>),
+    BeaconBlock(SignedBeaconBlock<C>),
     BeaconAttestation(Attestation<C>),
 }
 
@@ -106,7 +106,7 @@ pub struct Sender<C: Config>(UnboundedSender<Gossip<C>>);
 pub struct Receiver<C: Config>(UnboundedReceiver<Gossip<C>>);
 
 impl<C: Config> Network<C> for Sender<C> {
-    fn publish_beacon_block(&self, beacon_block: BeaconBlock<C>) -> Result<()> {
+    fn publish_beacon_block(&self, beacon_block: SignedBeaconBlock<C>) -> Result<()> {
         self.0
             .unbounded_send(Gossip::BeaconBlock(beacon_block))
             .map_err(Into::into)
@@ -279,11 +279,11 @@ impl<C: Config, N: Networked<C>> EventHandler<C, N> {
                     // [specification]: https://github.com/ethereum/eth2.0-specs/blob/19fa53709a247df5279f063179cc5e317ad57041/specs/networking/p2p-interface.md
                     // [introduced]:    https://github.com/ethereum/eth2.0-specs/pull/1404
                     iter::successors(networked.get_beacon_block(head_block_root), |previous| {
-                        networked.get_beacon_block(previous.parent_root)
+                        networked.get_beacon_block(previous.message.parent_root)
                     })
-                    .skip_while(|block| end_slot < block.slot)
-                    .take_while(|block| start_slot <= block.slot)
-                    .filter(|block| (block.slot - start_slot) % step == 0)
+                    .skip_while(|block| end_slot < block.message.slot)
+                    .take_while(|block| start_slot <= block.message.slot)
+                    .filter(|block| (block.message.slot - start_slot) % step == 0)
                     .for_each(|block| {
                         info!(
                             "sending BlocksByRange response chunk (peer_id: {}, block: {:?})",
@@ -405,8 +405,8 @@ impl<C: Config, N: Networked<C>> EventHandler<C, N> {
                     Hs(bytes.as_slice()),
                 );
 
-                let beacon_block =
-                    BeaconBlock::from_ssz_bytes(bytes.as_slice()).map_err(DebugAsError::new)?;
+                let beacon_block = SignedBeaconBlock::from_ssz_bytes(bytes.as_slice())
+                    .map_err(DebugAsError::new)?;
 
                 info!(
                     "decoded BlocksByRange response chunk (peer_id: {}, beacon_block: {:?})",
@@ -453,10 +453,9 @@ impl<C: Config, N: Networked<C>> EventHandler<C, N> {
         let request_id = self.request_id()?;
 
         Ok(Box::new(
-            self.lock_networked()
-                .join(self.lock_service())
-                .map(move |(networked, mut service)| {
-                    let status = networked.get_status();
+            self.lock_networked().join(self.lock_service()).and_then(
+                move |(networked, mut service)| {
+                    let status = networked.get_status()?;
 
                     info!(
                         "sending Status request (peer_id: {}, status: {:?})",
@@ -470,7 +469,10 @@ impl<C: Config, N: Networked<C>> EventHandler<C, N> {
                             RPCRequest::Status(status_into_status_message(status)),
                         ),
                     );
-                }),
+
+                    Ok(())
+                },
+            ),
         ))
     }
 
@@ -485,8 +487,8 @@ impl<C: Config, N: Networked<C>> EventHandler<C, N> {
             PubsubMessage::Block(bytes) => {
                 info!("received beacon block as gossip: {}", Hs(bytes.as_slice()));
 
-                let beacon_block =
-                    BeaconBlock::from_ssz_bytes(bytes.as_slice()).map_err(DebugAsError::new)?;
+                let beacon_block = SignedBeaconBlock::from_ssz_bytes(bytes.as_slice())
+                    .map_err(DebugAsError::new)?;
 
                 info!("decoded gossiped beacon block: {:?}", beacon_block);
 
@@ -658,7 +660,7 @@ fn get_and_check_status<C: Config, N: Networked<C>>(
     networked: &N,
     remote: Status,
 ) -> Result<Status> {
-    let local = networked.get_status();
+    let local = networked.get_status()?;
     ensure!(
         local.fork_version == remote.fork_version,
         EventHandlerError::ForkVersionMismatch {
@@ -28,6 +28,8 @@ pub fn get_previous_epoch<C: Config>(state: &BeaconState<C>) -> Epoch {
     }
 }
 
+// Only roots of the last ``SLOTS_PER_HISTORICAL_ROOT`` slots before ``state.slot`` are available.
+// Epochs whose start slot is outside that window result in ``SlotOutOfRange``.
 pub fn get_block_root<C: Config>(state: &BeaconState<C>, epoch: Epoch) -> Result<H256, Error> {
     get_block_root_at_slot::<C>(state, compute_start_slot_at_epoch::<C>(epoch))
 }
@@ -108,6 +110,29 @@ pub fn get_validator_churn_limit<C: Config>(state: &BeaconState<C>) -> Result<u6
     ))
 }
 
+// The exit epoch ``initiate_validator_exit`` would assign to a validator exiting now.
+// Exits are processed in order and at most ``get_validator_churn_limit`` validators exit per epoch.
+pub fn compute_exit_queue_epoch<C: Config>(state: &BeaconState<C>) -> Result<Epoch, Error> {
+    let exit_epochs = state
+        .validators
+        .iter()
+        .map(|validator| validator.exit_epoch)
+        .filter(|exit_epoch| *exit_epoch != FAR_FUTURE_EPOCH);
+
+    let mut exit_queue_epoch = exit_epochs.clone().fold(
+        compute_activation_exit_epoch::<C>(get_current_epoch(state)),
+        max,
+    );
+    let exit_queue_churn = exit_epochs
+        .filter(|exit_epoch| *exit_epoch == exit_queue_epoch)
+        .count() as u64;
+
+    if exit_queue_churn >= get_validator_churn_limit(state)? {
+        exit_queue_epoch += 1;
+    }
+    Ok(exit_queue_epoch)
+}
+
 pub fn get_seed<C: Config>(
     state: &BeaconState<C>,
     epoch: Epoch,
@@ -125,9 +150,14 @@ pub fn get_seed<C: Config>(
     }
     let epoch_b = epoch_bytes.expect("Expected valid conversion");
 
+    // `epoch + EPOCHS_PER_HISTORICAL_VECTOR` overflows for epochs near `FAR_FUTURE_EPOCH`.
+    // `get_randao_mix` reduces the epoch modulo the vector length anyway, so reduce it first.
+    let epochs_per_historical_vector = C::EpochsPerHistoricalVector::U64;
     let mix = get_randao_mix(
         state,
-        epoch + C::EpochsPerHistoricalVector::U64 - C::min_seed_lookahead() - 1,
+        epoch % epochs_per_historical_vector + epochs_per_historical_vector
+            - C::min_seed_lookahead()
+            - 1,
     );
     if mix.is_err() {
         return Err(mix.err().expect("Should be error"));
@@ -207,22 +237,15 @@ pub fn get_total_balance<C: Config>(
     state: &BeaconState<C>,
     indices: &[ValidatorIndex],
 ) -> Result<u64, Error> {
-    let mut balance: Gwei = 0;
-    for (i, v) in state.validators.iter().enumerate() {
-        if indices.contains(&(i as u64)) {
-            balance += v.effective_balance;
-        }
-    }
-    if balance > 1 {
-        Ok(balance)
-    } else {
-        Ok(1)
-    }
+    Ok(max(sum_effective_balances(&state.validators, indices), 1))
 }
 
 pub fn get_total_active_balance<C: Config>(state: &BeaconState<C>) -> Result<u64, Error> {
     let current_epoch = get_current_epoch(state);
-    get_total_balance(state, &get_active_validator_indices(state, current_epoch))
+    let total_balance =
+        get_total_balance(state, &get_active_validator_indices(state, current_epoch))?;
+    // Keeps reward calculations well-defined when no validators are active.
+    Ok(max(total_balance, C::effective_balance_increment()))
 }
 
 pub fn get_domain<C: Config>(
@@ -235,11 +258,7 @@ pub fn get_domain<C: Config>(
     } else {
         message_epoch.expect("Expected a value")
     };
-    let fork_version = if epoch < state.fork.epoch {
-        state.fork.previous_version
-    } else {
-        state.fork.current_version
-    };
+    let fork_version = state.fork.version_at_epoch(epoch);
     compute_domain(domain_type, Some(&fork_version))
 }
 
@@ -263,16 +282,15 @@ pub fn get_attesting_indices<C: Config>(
     attestation_data: &AttestationData,
     bitlist: &BitList<C::MaxValidatorsPerCommittee>,
 ) -> Result<BTreeSet<ValidatorIndex>, Error> {
-    let comittee = get_beacon_committee(state, attestation_data.slot, attestation_data.index);
-    if comittee.is_err() {
-        return Err(comittee.err().expect("Expected success"));
+    let comittee = get_beacon_committee(state, attestation_data.slot, attestation_data.index)?;
+    if bitlist.len() != comittee.len() {
+        return Err(Error::CommitteeLengthMismatch {
+            expected: comittee.len(),
+            got: bitlist.len(),
+        });
     }
     let mut validators: BTreeSet<ValidatorIndex> = BTreeSet::new();
-    for (i, v) in comittee
-        .expect("Expected success getting committee")
-        .into_iter()
-        .enumerate()
-    {
+    for (i, v) in comittee.into_iter().enumerate() {
         if bitlist
             .get(i)
             .expect("bitfield length should match committee size")
@@ -287,7 +305,7 @@ pub fn get_attesting_indices<C: Config>(
 mod tests {
     use super::*;
     use ssz_types::{typenum, FixedVector, VariableList};
-    use types::config::MinimalConfig;
+    use types::config::{CustomTestnetConfig, MainnetConfig, MinimalConfig};
     use types::types::Validator;
 
     #[test]
@@ -312,6 +330,42 @@ mod tests {
         assert_eq!(result.is_ok(), false);
     }
 
+    #[test]
+    fn test_get_block_root_agrees_with_get_block_root_at_slot() {
+        let mut state = BeaconState::<MinimalConfig>::default();
+        let base: Vec<H256> = (0..64).map(H256::from_low_u64_be).collect();
+        let roots: FixedVector<_, typenum::U64> = FixedVector::from(base);
+        state.block_roots = roots;
+        state.slot = 64;
+        for epoch in 0..8 {
+            let slot = compute_start_slot_at_epoch::<MinimalConfig>(epoch);
+            assert_eq!(
+                get_block_root::<MinimalConfig>(&state, epoch),
+                get_block_root_at_slot::<MinimalConfig>(&state, slot),
+            );
+            assert_eq!(
+                get_block_root::<MinimalConfig>(&state, epoch),
+                Ok(H256::from_low_u64_be(slot)),
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_block_root_outside_historical_window() {
+        let mut state = BeaconState::<MinimalConfig>::default();
+        state.slot = 72;
+        // Slot 8 is exactly `SlotsPerHistoricalRoot` slots before `state.slot`.
+        assert!(get_block_root::<MinimalConfig>(&state, 1).is_ok());
+        assert_eq!(
+            get_block_root::<MinimalConfig>(&state, 0),
+            Err(Error::SlotOutOfRange)
+        );
+        assert_eq!(
+            get_block_root::<MinimalConfig>(&state, 9),
+            Err(Error::SlotOutOfRange)
+        );
+    }
+
     #[test]
     fn test_get_block_root_at_slot() {
         let mut state = BeaconState::<MinimalConfig>::default();
@@ -332,6 +386,30 @@ mod tests {
         assert_eq!(result.is_ok(), true);
     }
 
+    #[test]
+    fn test_get_seed() {
+        let mut state = BeaconState::<MinimalConfig>::default();
+        for (index, mix) in state.randao_mixes.iter_mut().enumerate() {
+            *mix = H256::from_low_u64_be(index as u64);
+        }
+        let domain_type = MinimalConfig::domain_beacon_proposer();
+        let expected_seed = |epoch: Epoch, mix_index: u64| {
+            let mut seed = vec![];
+            seed.extend_from_slice(&domain_type.to_le_bytes());
+            seed.extend_from_slice(&epoch.to_le_bytes());
+            seed.extend_from_slice(H256::from_low_u64_be(mix_index).as_bytes());
+            H256::from_slice(&hash(&seed))
+        };
+
+        // 0 + 64 - 1 - 1 = 62
+        assert_eq!(get_seed(&state, 0, domain_type), Ok(expected_seed(0, 62)));
+        // (2^64 - 1) % 64 + 64 - 1 - 1 = 125, which wraps around to 61.
+        assert_eq!(
+            get_seed(&state, FAR_FUTURE_EPOCH, domain_type),
+            Ok(expected_seed(FAR_FUTURE_EPOCH, 61))
+        );
+    }
+
     #[test]
     fn test_get_validator_churn_limit() {
         let state = BeaconState::<MinimalConfig>::default();
@@ -342,6 +420,113 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compute_exit_queue_epoch() {
+        // compute_activation_exit_epoch(0) = 0 + 1 + MIN_SEED_LOOKAHEAD = 2
+        let mut state = state_with_active_validators::<MinimalConfig>(8);
+        assert_eq!(compute_exit_queue_epoch(&state), Ok(2));
+
+        // Exits scheduled before the earliest possible exit epoch do not delay new ones.
+        state.validators[0].exit_epoch = 1;
+        assert_eq!(compute_exit_queue_epoch(&state), Ok(2));
+
+        // The queue is below the churn limit of 4 (MIN_PER_EPOCH_CHURN_LIMIT).
+        for validator in state.validators.iter_mut().skip(1).take(3) {
+            validator.exit_epoch = 5;
+        }
+        assert_eq!(compute_exit_queue_epoch(&state), Ok(5));
+
+        // The queue is saturated, so the next exit is pushed to the following epoch.
+        state.validators[4].exit_epoch = 5;
+        assert_eq!(compute_exit_queue_epoch(&state), Ok(6));
+    }
+
+    fn state_with_active_validators<C: Config>(count: usize) -> BeaconState<C> {
+        let validator = Validator {
+            exit_epoch: FAR_FUTURE_EPOCH,
+            ..Validator::default()
+        };
+        BeaconState {
+            validators: VariableList::from(vec![validator; count]),
+            ..BeaconState::default()
+        }
+    }
+
+    #[test]
+    fn test_get_committee_count_at_slot() {
+        // 16384 / SLOTS_PER_EPOCH / TARGET_COMMITTEE_SIZE = 16384 / 32 / 128 = 4
+        let state = state_with_active_validators::<MainnetConfig>(16384);
+        assert_eq!(get_committee_count_at_slot(&state, 0), Ok(4));
+
+        // There is always at least one committee per slot.
+        let state = state_with_active_validators::<MainnetConfig>(100);
+        assert_eq!(get_committee_count_at_slot(&state, 0), Ok(1));
+
+        // 1024 / 8 / 4 = 32 is capped at MAX_COMMITTEES_PER_SLOT = 4.
+        let state = state_with_active_validators::<MinimalConfig>(1024);
+        assert_eq!(
+            get_committee_count_at_slot(&state, 0),
+            Ok(MinimalConfig::max_committees_per_slot())
+        );
+    }
+
+    #[test]
+    fn test_get_committee_count_at_slot_with_custom_config() {
+        // 24 / SLOTS_PER_EPOCH / TARGET_COMMITTEE_SIZE = 24 / 4 / 2 = 3
+        let state = state_with_active_validators::<CustomTestnetConfig>(24);
+        assert_eq!(get_committee_count_at_slot(&state, 0), Ok(3));
+
+        // The same validators only make up one committee per slot with the minimal parameters.
+        let state = state_with_active_validators::<MinimalConfig>(24);
+        assert_eq!(get_committee_count_at_slot(&state, 0), Ok(1));
+    }
+
+    #[test]
+    fn test_get_beacon_proposer_index() {
+        let mut state = state_with_active_validators::<MinimalConfig>(8);
+        for (index, validator) in state.validators.iter_mut().enumerate() {
+            validator.effective_balance = if index % 3 == 0 {
+                MinimalConfig::max_effective_balance()
+            } else {
+                1_000_000_000
+            };
+        }
+
+        // Computed with the Python definitions from the specification.
+        let expected = [3, 0, 0, 6, 6, 6, 4, 6];
+        for (slot, expected_index) in expected.iter().enumerate() {
+            state.slot = slot as Slot;
+            assert_eq!(get_beacon_proposer_index(&state), Ok(*expected_index));
+        }
+    }
+
+    #[test]
+    fn test_get_total_active_balance_without_active_validators() {
+        let mut state = state_with_active_validators::<MinimalConfig>(4);
+        for validator in state.validators.iter_mut() {
+            validator.effective_balance = MinimalConfig::max_effective_balance();
+            validator.exit_epoch = 0;
+        }
+        assert_eq!(
+            get_total_active_balance(&state),
+            Ok(MinimalConfig::effective_balance_increment())
+        );
+    }
+
+    #[test]
+    fn test_get_attesting_indices_committee_length_mismatch() {
+        // With 8 validators every committee in the minimal configuration has a single member.
+        let state = state_with_active_validators::<MinimalConfig>(8);
+        let bitlist = BitList::with_capacity(2).expect("Expected success");
+        assert_eq!(
+            get_attesting_indices(&state, &AttestationData::default(), &bitlist),
+            Err(Error::CommitteeLengthMismatch {
+                expected: 1,
+                got: 2,
+            })
+        );
+    }
+
     #[test]
     fn test_get_total_balance() {
         let mut state = BeaconState::<MinimalConfig>::default();
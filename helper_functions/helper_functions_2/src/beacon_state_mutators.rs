@@ -1,7 +1,5 @@
 use super::beacon_state_accessors as accessors;
 use crate::beacon_state_accessors::get_current_epoch;
-use crate::beacon_state_accessors::get_validator_churn_limit;
-use crate::misc::compute_activation_exit_epoch;
 use std::cmp;
 use std::convert::TryFrom;
 use typenum::Unsigned;
@@ -69,9 +67,13 @@ pub fn slash_validator<C: Config>(
         Some(i) => i,
     };
     let whistleblower_reward = effective_balance / C::whistleblower_reward_quotient();
-    let proposer_reward = effective_balance / C::proposer_reward_quotient();
+    let proposer_reward = whistleblower_reward / C::proposer_reward_quotient();
     increase_balance(state, proposer_index, proposer_reward)?;
-    increase_balance(state, whistleblower_ind_val, whistleblower_reward)?;
+    increase_balance(
+        state,
+        whistleblower_ind_val,
+        whistleblower_reward - proposer_reward,
+    )?;
     Ok(())
 }
 
@@ -83,40 +85,9 @@ pub fn initiate_validator_exit<C: Config>(
     if validator.exit_epoch != FAR_FUTURE_EPOCH {
         return Ok(());
     }
-    let validators_number = state.validators.len();
-
-    // get exit epochs of all validators
-    let mut exit_epochs: Vec<Epoch> = Vec::with_capacity(validators_number);
-    for i in 0..validators_number {
-        if state.validators[i].exit_epoch != FAR_FUTURE_EPOCH {
-            exit_epochs.push(state.validators[i].exit_epoch);
-        }
-    }
-
-    // get the possible exit epoch - by MIN_SEED_LOOK_AHEAD or the last validator in queue:
-    let current_epoch: Epoch = get_current_epoch(state);
-    let mut exit_queue_epoch: Epoch = compute_activation_exit_epoch::<C>(current_epoch);
-    let iter = exit_epochs.iter();
-    for i in iter {
-        if *i > exit_queue_epoch {
-            exit_queue_epoch = *i;
-        }
-    }
-
-    // check if number of exiting validators does not exceed churn limit
-    let mut exit_queue_churn = 0;
-    let iter = exit_epochs.iter();
-    for i in iter {
-        if *i == exit_queue_epoch {
-            exit_queue_churn += 1;
-        }
-    }
-    if exit_queue_churn >= get_validator_churn_limit(state).expect("Expected success") {
-        exit_queue_epoch += 1;
-    }
 
     // change validator's exit epoch in the beacon chain
-    validator.exit_epoch = exit_queue_epoch;
+    validator.exit_epoch = accessors::compute_exit_queue_epoch(state)?;
     validator.withdrawable_epoch = validator.exit_epoch + C::min_validator_withdrawability_delay();
     state.validators[usize::try_from(index).expect("")] = validator;
     Ok(())
@@ -170,6 +141,64 @@ mod tests {
                 state.validators[0].exit_epoch
             );
         }
+
+        #[test]
+        fn test_rewards_and_penalties() {
+            let max_effective_balance = MinimalConfig::max_effective_balance();
+            let validator = Validator {
+                effective_balance: max_effective_balance,
+                exit_epoch: FAR_FUTURE_EPOCH,
+                withdrawable_epoch: FAR_FUTURE_EPOCH,
+                ..default_validator()
+            };
+            let mut state = BeaconState::<MinimalConfig>::default();
+            for _ in 0..8 {
+                state
+                    .validators
+                    .push(validator.clone())
+                    .expect("Expected success");
+                state
+                    .balances
+                    .push(max_effective_balance)
+                    .expect("Expected success");
+            }
+
+            let proposer_index =
+                accessors::get_beacon_proposer_index(&state).expect("Expected success");
+            let slashed_index = (proposer_index + 1) % 8;
+            let whistleblower_index = (proposer_index + 2) % 8;
+
+            slash_validator(&mut state, slashed_index, Some(whistleblower_index))
+                .expect("slash_validator should succeed");
+
+            let slashed = &state.validators[slashed_index as usize];
+            assert!(slashed.slashed);
+            assert_eq!(
+                slashed.withdrawable_epoch,
+                cmp::max(
+                    slashed.exit_epoch + MinimalConfig::min_validator_withdrawability_delay(),
+                    <MinimalConfig as Config>::EpochsPerSlashingsVector::to_u64(),
+                )
+            );
+            assert_eq!(state.slashings[0], max_effective_balance);
+
+            let whistleblower_reward =
+                max_effective_balance / MinimalConfig::whistleblower_reward_quotient();
+            let proposer_reward = whistleblower_reward / MinimalConfig::proposer_reward_quotient();
+            assert_eq!(
+                state.balances[slashed_index as usize],
+                max_effective_balance
+                    - max_effective_balance / MinimalConfig::min_slashing_penalty_quotient()
+            );
+            assert_eq!(
+                state.balances[proposer_index as usize],
+                max_effective_balance + proposer_reward
+            );
+            assert_eq!(
+                state.balances[whistleblower_index as usize],
+                max_effective_balance + whistleblower_reward - proposer_reward
+            );
+        }
     }
 
     #[test]
@@ -230,4 +259,30 @@ mod tests {
         decrease_balance(&mut state, 1, 5).expect("");
         assert_eq!(state.balances[1], 5);
     }
+
+    #[test]
+    fn test_increase_and_decrease_balance_are_inverse() {
+        let mut state = BeaconState::<MinimalConfig>::default();
+        state.balances.push(5).expect("");
+        state.balances.push(7).expect("");
+        increase_balance(&mut state, 1, 10).expect("");
+        decrease_balance(&mut state, 1, 10).expect("");
+        assert_eq!(state.balances[0], 5);
+        assert_eq!(state.balances[1], 7);
+    }
+
+    #[test]
+    fn test_balance_index_out_of_range() {
+        let mut state = BeaconState::<MinimalConfig>::default();
+        state.balances.push(5).expect("");
+        assert_eq!(
+            increase_balance(&mut state, 1, 10),
+            Err(Error::IndexOutOfRange)
+        );
+        assert_eq!(
+            decrease_balance(&mut state, 1, 10),
+            Err(Error::IndexOutOfRange)
+        );
+        assert_eq!(state.balances[0], 5);
+    }
 }
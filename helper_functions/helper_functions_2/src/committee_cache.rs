@@ -0,0 +1,116 @@
+use crate::beacon_state_accessors::get_beacon_committee;
+use ssz_types::BitList;
+use std::collections::{hash_map::Entry, BTreeSet, HashMap};
+use types::beacon_state::BeaconState;
+use types::config::Config;
+use types::helper_functions_types::Error;
+use types::primitives::*;
+use types::types::{Attestation, AttestationData, IndexedAttestation};
+
+/// Memoizes `get_beacon_committee` results for a single state.
+///
+/// Committees only depend on the active validator set and RANDAO mixes, neither of which change
+/// while the operations in a block are processed, so one cache can be shared by all of them.
+#[derive(Default)]
+pub struct CommitteeCache {
+    committees: HashMap<(Slot, CommitteeIndex), Vec<ValidatorIndex>>,
+}
+
+impl CommitteeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_beacon_committee<C: Config>(
+        &mut self,
+        state: &BeaconState<C>,
+        slot: Slot,
+        index: CommitteeIndex,
+    ) -> Result<&[ValidatorIndex], Error> {
+        match self.committees.entry((slot, index)) {
+            Entry::Occupied(occupied) => Ok(occupied.into_mut()),
+            Entry::Vacant(vacant) => Ok(vacant.insert(get_beacon_committee(state, slot, index)?)),
+        }
+    }
+
+    pub fn get_attesting_indices<C: Config>(
+        &mut self,
+        state: &BeaconState<C>,
+        attestation_data: &AttestationData,
+        bitlist: &BitList<C::MaxValidatorsPerCommittee>,
+    ) -> Result<BTreeSet<ValidatorIndex>, Error> {
+        let committee =
+            self.get_beacon_committee(state, attestation_data.slot, attestation_data.index)?;
+        if bitlist.len() != committee.len() {
+            return Err(Error::CommitteeLengthMismatch {
+                expected: committee.len(),
+                got: bitlist.len(),
+            });
+        }
+        Ok(committee
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                bitlist
+                    .get(*i)
+                    .expect("bitfield length should match committee size")
+            })
+            .map(|(_, index)| *index)
+            .collect())
+    }
+
+    pub fn get_indexed_attestation<C: Config>(
+        &mut self,
+        state: &BeaconState<C>,
+        attestation: &Attestation<C>,
+    ) -> Result<IndexedAttestation<C>, Error> {
+        let attesting_indices =
+            self.get_attesting_indices(state, &attestation.data, &attestation.aggregation_bits)?;
+
+        Ok(IndexedAttestation {
+            attesting_indices: attesting_indices.into_iter().collect::<Vec<_>>().into(),
+            data: attestation.data.clone(),
+            signature: attestation.signature.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon_state_accessors::get_committee_count_at_slot;
+    use ssz_types::VariableList;
+    use typenum::Unsigned as _;
+    use types::config::MinimalConfig;
+    use types::consts::FAR_FUTURE_EPOCH;
+    use types::types::Validator;
+
+    #[test]
+    fn test_cached_committees_match_uncached() {
+        let validator = Validator {
+            effective_balance: MinimalConfig::max_effective_balance(),
+            exit_epoch: FAR_FUTURE_EPOCH,
+            withdrawable_epoch: FAR_FUTURE_EPOCH,
+            ..Validator::default()
+        };
+        let state = BeaconState::<MinimalConfig> {
+            validators: VariableList::from(vec![validator; 64]),
+            ..BeaconState::default()
+        };
+        let mut cache = CommitteeCache::new();
+
+        for slot in 0..<MinimalConfig as Config>::SlotsPerEpoch::U64 {
+            let count = get_committee_count_at_slot(&state, slot).expect("Expected success");
+            for index in 0..count {
+                let uncached = get_beacon_committee(&state, slot, index).expect("Expected success");
+                // The second lookup is served from the cache.
+                for _ in 0..2 {
+                    let cached = cache
+                        .get_beacon_committee(&state, slot, index)
+                        .expect("Expected success");
+                    assert_eq!(cached, uncached.as_slice());
+                }
+            }
+        }
+    }
+}
@@ -6,7 +6,8 @@ use ring::digest::{digest, SHA256};
 use ssz::DecodeError;
 use std::convert::TryInto;
 use tree_hash::{SignedRoot, TreeHash};
-use types::primitives::H256;
+use types::helper_functions_types::Error;
+use types::primitives::{Domain, H256};
 
 pub fn hash(input: &[u8]) -> Vec<u8> {
     digest(&SHA256, input).as_ref().into()
@@ -49,6 +50,22 @@ pub fn bls_aggregate_pubkeys(pubkeys: &[PublicKey]) -> AggregatePublicKey {
     aggr_pk
 }
 
+// Verify that ``signature`` is an aggregate of signatures of ``message`` by all of ``pubkeys``.
+// The signature is verified against the aggregate public key directly, so an empty ``pubkeys`` or
+// a signature at infinity makes verification fail rather than return an error.
+pub fn aggregate_verify(
+    pubkeys: &[&PublicKey],
+    message: &H256,
+    signature: &AggregateSignature,
+    domain: Domain,
+) -> Result<bool, Error> {
+    let mut aggregate_pubkey = AggregatePublicKey::new();
+    for pubkey in pubkeys {
+        aggregate_pubkey.add(pubkey);
+    }
+    Ok(signature.verify(message.as_bytes(), domain, &aggregate_pubkey))
+}
+
 pub fn hash_tree_root<T: TreeHash>(object: &T) -> H256 {
     let hash = object.tree_hash_root();
     H256::from_slice(hash.as_slice())
@@ -156,6 +173,58 @@ mod tests {
         assert_eq!(bls_verify(&pk_bytes, message, &sg_bytes, 1), Err(err));
     }
 
+    #[test]
+    fn test_aggregate_verify() {
+        let domain: Domain = 45;
+        let message = H256::repeat_byte(111);
+        let secret_keys = (0..3).map(|_| SecretKey::random()).collect::<Vec<_>>();
+        let public_keys = secret_keys
+            .iter()
+            .map(PublicKey::from_secret_key)
+            .collect::<Vec<_>>();
+        let public_key_refs = public_keys.iter().collect::<Vec<_>>();
+
+        let mut signature = AggregateSignature::new();
+        for secret_key in &secret_keys {
+            signature.add(&Signature::new(message.as_bytes(), domain, secret_key));
+        }
+
+        assert_eq!(
+            aggregate_verify(&public_key_refs, &message, &signature, domain),
+            Ok(true)
+        );
+        assert_eq!(
+            aggregate_verify(
+                &public_key_refs,
+                &H256::repeat_byte(222),
+                &signature,
+                domain
+            ),
+            Ok(false)
+        );
+        assert_eq!(
+            aggregate_verify(&public_key_refs, &message, &signature, domain + 1),
+            Ok(false)
+        );
+        assert_eq!(
+            aggregate_verify(&public_key_refs[..2], &message, &signature, domain),
+            Ok(false)
+        );
+        assert_eq!(
+            aggregate_verify(&[], &message, &signature, domain),
+            Ok(false)
+        );
+        assert_eq!(
+            aggregate_verify(
+                &public_key_refs,
+                &message,
+                &AggregateSignature::new(),
+                domain
+            ),
+            Ok(false)
+        );
+    }
+
     #[test]
     fn test_verify_multiple() {
         let domain: u64 = 45;
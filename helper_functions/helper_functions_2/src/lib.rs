@@ -4,6 +4,7 @@
 
 pub mod beacon_state_accessors;
 pub mod beacon_state_mutators;
+pub mod committee_cache;
 pub mod crypto;
 pub mod error;
 pub mod math;
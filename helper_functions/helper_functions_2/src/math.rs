@@ -1,6 +1,8 @@
 use integer_sqrt::IntegerSquareRoot as _;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use types::helper_functions_types::Error;
+use types::primitives::{Gwei, ValidatorIndex};
+use types::types::Validator;
 
 // inteface has changed
 pub fn xor_str(bytes_1: &str, bytes_2: &str) -> String {
@@ -23,23 +25,20 @@ pub fn xor_str(bytes_1: &str, bytes_2: &str) -> String {
     string_to_return
 }
 
-pub fn xor(bytes_1: &[u8; 32], bytes_2: &[u8; 32]) -> Vec<u8> {
-    let mut vec_to_return: Vec<u8> = Vec::new();
-    for i in 0..32 {
-        vec_to_return.push(bytes_1[i] ^ bytes_2[i]);
-    }
-    vec_to_return
+pub fn xor(bytes_1: &[u8; 32], bytes_2: &[u8; 32]) -> [u8; 32] {
+    let mut array_to_return = [0; 32];
+    for (byte, (byte_1, byte_2)) in array_to_return
+        .iter_mut()
+        .zip(bytes_1.iter().zip(bytes_2.iter()))
+    {
+        *byte = byte_1 ^ byte_2;
+    }
+    array_to_return
 }
 
+// Return the largest integer ``x`` such that ``x ** 2 <= n``.
+// Correct for the whole `u64` range, unlike a naive Newton's method or a conversion to `f64`.
 pub fn integer_squareroot(n: u64) -> u64 {
-    /*
-    let sqrt = (n as f64).sqrt();
-    let mut sqrt_floor = sqrt as u64;
-    if (sqrt_floor + 1) * (sqrt_floor + 1) <= n {
-        sqrt_floor += 1;
-    }
-    sqrt_floor
-     */
     n.integer_sqrt()
 }
 
@@ -77,10 +76,23 @@ pub fn bytes_to_int(bytes: &[u8]) -> Result<u64, Error> {
     Ok(result)
 }
 
+// Sum the effective balances of the validators at ``indices``.
+// The sum is accumulated in a `u128` and saturates at `u64::max_value()`.
+pub fn sum_effective_balances(validators: &[Validator], indices: &[ValidatorIndex]) -> Gwei {
+    let total: u128 = validators
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| indices.contains(&(*i as u64)))
+        .map(|(_, v)| u128::from(v.effective_balance))
+        .sum();
+    Gwei::try_from(total).unwrap_or(Gwei::max_value())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ethereum_types::U256;
+    use bls::{PublicKey, SecretKey};
+    use ethereum_types::{H256, U256};
 
     #[test]
     fn test_xor_str() {
@@ -136,7 +148,28 @@ mod tests {
         let v1_int = U256::from(v1);
         let v2_int = U256::from(v2);
         let expected = v1_int ^ v2_int;
-        assert_eq!(expected, U256::from(xor(&v1, &v2).as_slice()));
+        assert_eq!(expected, U256::from(xor(&v1, &v2)));
+        assert_eq!(xor(&v1, &v1), [0; 32]);
+        assert_eq!(xor(&[0xff; 32], &[0x0f; 32]), [0xf0; 32]);
+    }
+
+    #[test]
+    fn test_integer_squareroot() {
+        assert_eq!(integer_squareroot(0), 0);
+        assert_eq!(integer_squareroot(1), 1);
+        assert_eq!(integer_squareroot(15), 3);
+        assert_eq!(integer_squareroot(16), 4);
+        assert_eq!(integer_squareroot(17), 4);
+        assert_eq!(integer_squareroot(999_999), 999);
+        assert_eq!(integer_squareroot(1_000_000), 1000);
+    }
+
+    #[test]
+    fn test_integer_squareroot_large_inputs() {
+        let max_root = u64::from(u32::max_value());
+        assert_eq!(integer_squareroot(max_root * max_root), max_root);
+        assert_eq!(integer_squareroot(max_root * max_root - 1), max_root - 1);
+        assert_eq!(integer_squareroot(u64::max_value()), max_root);
     }
 
     #[test]
@@ -157,4 +190,45 @@ mod tests {
         let num: u64 = bytes_to_int(&[1, 1]).expect("");
         assert_eq!(num, 257);
     }
+
+    fn validator_with_balance(effective_balance: Gwei) -> Validator {
+        Validator {
+            effective_balance,
+            slashed: false,
+            activation_eligibility_epoch: 0,
+            activation_epoch: 0,
+            exit_epoch: 0,
+            withdrawable_epoch: 0,
+            withdrawal_credentials: H256([0; 32]),
+            pubkey: PublicKey::from_secret_key(&SecretKey::random()),
+        }
+    }
+
+    #[test]
+    fn test_sum_effective_balances() {
+        let validators = [
+            validator_with_balance(1),
+            validator_with_balance(2),
+            validator_with_balance(4),
+        ];
+        assert_eq!(sum_effective_balances(&validators, &[0, 2]), 5);
+        assert_eq!(sum_effective_balances(&validators, &[]), 0);
+    }
+
+    #[test]
+    fn test_sum_effective_balances_saturates() {
+        let validators = [
+            validator_with_balance(u64::max_value() / 2 + 1),
+            validator_with_balance(u64::max_value() / 2 + 1),
+            validator_with_balance(1),
+        ];
+        assert_eq!(
+            sum_effective_balances(&validators, &[0]),
+            u64::max_value() / 2 + 1
+        );
+        assert_eq!(
+            sum_effective_balances(&validators, &[0, 1, 2]),
+            u64::max_value()
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use crate::crypto::hash;
+use crate::crypto::{hash, hash_tree_root};
 use crate::math::bytes_to_int;
 use crate::math::int_to_bytes;
 
@@ -9,6 +9,7 @@ use types::beacon_state::BeaconState;
 use types::config::Config;
 use types::helper_functions_types::Error;
 use types::primitives::{Domain, DomainType, Epoch, Slot, ValidatorIndex, Version, H256};
+use types::types::ForkData;
 
 pub fn compute_epoch_at_slot<C: Config>(slot: Slot) -> Epoch {
     slot / C::SlotsPerEpoch::to_u64()
@@ -18,6 +19,12 @@ pub fn compute_start_slot_at_epoch<C: Config>(epoch: Epoch) -> Slot {
     epoch * C::SlotsPerEpoch::to_u64()
 }
 
+// Like ``compute_start_slot_at_epoch``, but returns ``None`` instead of overflowing.
+// Use this when ``epoch`` comes from an object received from the network.
+pub fn compute_start_slot_at_epoch_checked<C: Config>(epoch: Epoch) -> Option<Slot> {
+    epoch.checked_mul(C::SlotsPerEpoch::to_u64())
+}
+
 pub fn compute_activation_exit_epoch<C: Config>(epoch: Epoch) -> Epoch {
     epoch + 1 + C::min_seed_lookahead()
 }
@@ -34,12 +41,26 @@ pub fn compute_domain(domain_type: DomainType, fork_version: Option<&Version>) -
     bytes_to_int(&domain_bytes).expect("")
 }
 
+pub fn compute_fork_data_root(current_version: Version, genesis_validators_root: H256) -> H256 {
+    hash_tree_root(&ForkData {
+        current_version,
+        genesis_validators_root,
+    })
+}
+
+pub fn compute_fork_digest(current_version: Version, genesis_validators_root: H256) -> [u8; 4] {
+    let mut fork_digest = [0; 4];
+    fork_digest
+        .copy_from_slice(&compute_fork_data_root(current_version, genesis_validators_root)[..4]);
+    fork_digest
+}
+
 pub fn compute_shuffled_index<C: Config>(
     index: ValidatorIndex,
     index_count: u64,
     seed: &H256,
 ) -> Result<ValidatorIndex, Error> {
-    if index > index_count {
+    if index >= index_count {
         return Err(Error::IndexOutOfRange);
     }
 
@@ -128,6 +149,10 @@ pub fn compute_committee<'a, C: Config>(
     index: u64,
     count: u64,
 ) -> Result<Vec<ValidatorIndex>, Error> {
+    // Otherwise the committee would extend past the end of `indices`.
+    if index >= count {
+        return Err(Error::CommitteeOutOfRange);
+    }
     let start = ((indices.len() as u64) * index) / count;
     let end = ((indices.len() as u64) * (index + 1)) / count;
     let mut committee_vec: Vec<ValidatorIndex> = Vec::new();
@@ -154,7 +179,7 @@ pub fn compute_committee<'a, C: Config>(
 mod tests {
     use super::*;
     use bls::{PublicKey, SecretKey};
-    use types::config::MinimalConfig;
+    use types::config::{MainnetConfig, MinimalConfig};
     use types::consts::FAR_FUTURE_EPOCH;
     use types::types::Validator;
 
@@ -173,6 +198,29 @@ mod tests {
         assert_ne!(compute_start_slot_at_epoch::<MinimalConfig>(1), 9);
     }
 
+    #[test]
+    fn test_start_slot_at_epoch_checked() {
+        let slots_per_epoch = <MinimalConfig as Config>::SlotsPerEpoch::to_u64();
+        let max_epoch = u64::max_value() / slots_per_epoch;
+
+        assert_eq!(
+            compute_start_slot_at_epoch_checked::<MinimalConfig>(1),
+            Some(8)
+        );
+        assert_eq!(
+            compute_start_slot_at_epoch_checked::<MinimalConfig>(max_epoch),
+            Some(max_epoch * slots_per_epoch)
+        );
+        assert_eq!(
+            compute_start_slot_at_epoch_checked::<MinimalConfig>(max_epoch + 1),
+            None
+        );
+        assert_eq!(
+            compute_start_slot_at_epoch_checked::<MinimalConfig>(FAR_FUTURE_EPOCH),
+            None
+        );
+    }
+
     #[test]
     fn test_activation_exit_epoch() {
         assert_eq!(compute_activation_exit_epoch::<MinimalConfig>(1), 3);
@@ -184,6 +232,35 @@ mod tests {
         assert_eq!(domain, 0x0001_0000_0001);
         // 1 * 256 ^ 4 + 1 = 4294967297 = 0x0001_0000_0001
     }
+    // The expected roots are `sha256(current_version + 28 zero bytes + genesis_validators_root)`.
+    #[test]
+    fn test_compute_fork_data_root() {
+        assert_eq!(
+            compute_fork_data_root([0, 0, 0, 0], H256::zero()),
+            "f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b"
+                .parse::<H256>()
+                .expect("")
+        );
+        assert_eq!(
+            compute_fork_data_root([0, 0, 0, 1], H256::repeat_byte(0x11)),
+            "4e5926cae4cb9995b5039820011dcb7bdda937dfdbc8f4973291b9308ce5649c"
+                .parse::<H256>()
+                .expect("")
+        );
+    }
+
+    #[test]
+    fn test_compute_fork_digest() {
+        assert_eq!(
+            compute_fork_digest([0, 0, 0, 0], H256::zero()),
+            [0xf5, 0xa5, 0xfd, 0x42]
+        );
+        assert_eq!(
+            compute_fork_digest([0, 0, 0, 1], H256::repeat_byte(0x11)),
+            [0x4e, 0x59, 0x26, 0xca]
+        );
+    }
+
     #[test]
     fn test_compute_shuffled_index() {
         let test_indices_length = 25;
@@ -201,6 +278,83 @@ mod tests {
         }
     }
 
+    // The expected values in the tests below were computed with the Python definitions from the
+    // specification using the minimal configuration (`SHUFFLE_ROUND_COUNT = 10`).
+
+    #[test]
+    fn test_compute_shuffled_index_vector() {
+        let seed = H256::repeat_byte(0x11);
+        let shuffled = (0..10)
+            .map(|index| compute_shuffled_index::<MinimalConfig>(index, 10, &seed).expect(""))
+            .collect::<Vec<_>>();
+        assert_eq!(shuffled, [2, 3, 8, 5, 7, 0, 1, 4, 9, 6]);
+        assert_eq!(
+            compute_shuffled_index::<MinimalConfig>(10, 10, &seed),
+            Err(Error::IndexOutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_shuffle_round_count() {
+        assert_eq!(MinimalConfig::shuffle_round_count(), 10);
+        assert_eq!(MainnetConfig::shuffle_round_count(), 90);
+
+        // Same input as above, shuffled with `SHUFFLE_ROUND_COUNT = 90`.
+        let seed = H256::repeat_byte(0x11);
+        let shuffled = (0..10)
+            .map(|index| compute_shuffled_index::<MainnetConfig>(index, 10, &seed).expect(""))
+            .collect::<Vec<_>>();
+        assert_eq!(shuffled, [1, 2, 3, 0, 4, 6, 8, 9, 5, 7]);
+    }
+
+    #[test]
+    fn test_compute_committee_vector() {
+        let indices = (100..110).collect::<Vec<ValidatorIndex>>();
+        let seed = H256::from_slice(&(0..32).collect::<Vec<u8>>());
+        let committees = (0..3)
+            .map(|index| compute_committee::<MinimalConfig>(&indices, &seed, index, 3).expect(""))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            committees,
+            [
+                vec![103, 108, 106],
+                vec![105, 107, 109],
+                vec![104, 102, 101, 100]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compute_proposer_index_vector() {
+        let mut state = BeaconState::<MinimalConfig>::default();
+        for index in 0..10 {
+            let effective_balance = if index % 3 == 0 {
+                MinimalConfig::max_effective_balance()
+            } else {
+                1_000_000_000
+            };
+            state
+                .validators
+                .push(Validator {
+                    effective_balance,
+                    ..Validator::default()
+                })
+                .expect("");
+        }
+        let indices = (0..10).collect::<Vec<ValidatorIndex>>();
+
+        // The first candidate is accepted.
+        assert_eq!(
+            compute_proposer_index(&state, &indices, &H256::repeat_byte(0)),
+            Ok(9)
+        );
+        // The first candidate has a low effective balance and is rejected.
+        assert_eq!(
+            compute_proposer_index(&state, &indices, &H256::repeat_byte(1)),
+            Ok(3)
+        );
+    }
+
     #[test]
     fn test_compute_proposer_index() {
         let mut state = BeaconState::<MinimalConfig>::default();
@@ -246,4 +400,17 @@ mod tests {
             compute_committee::<MinimalConfig>(&test_vec, &H256::random(), 2, 20).expect("");
         assert_eq!(5, committee.len());
     }
+
+    #[test]
+    fn test_compute_committee_out_of_range() {
+        let test_vec: Vec<ValidatorIndex> = (0..100).collect();
+        assert_eq!(
+            compute_committee::<MinimalConfig>(&test_vec, &H256::random(), 20, 20),
+            Err(Error::CommitteeOutOfRange)
+        );
+        assert_eq!(
+            compute_committee::<MinimalConfig>(&test_vec, &H256::random(), 0, 0),
+            Err(Error::CommitteeOutOfRange)
+        );
+    }
 }
@@ -1,16 +1,15 @@
 use crate::{beacon_state_accessors as accessors, crypto};
-use bls::AggregatePublicKey;
+use bls::PublicKey;
 use itertools::Itertools;
 use ssz_types::VariableList;
 use std::convert::TryFrom;
-use tree_hash::TreeHash;
 use typenum::Unsigned;
 use types::{
     beacon_state::BeaconState,
     config::Config,
     helper_functions_types::Error,
     primitives::{Epoch, H256},
-    types::{AttestationData, IndexedAttestation, Validator},
+    types::{AttestationData, BeaconBlock, IndexedAttestation, Validator},
 };
 
 type ValidatorIndexList<C> = VariableList<u64, <C as Config>::MaxValidatorsPerCommittee>;
@@ -41,26 +40,29 @@ where
     data.into_iter().tuple_windows().all(|(a, b)| a <= b)
 }
 
-fn aggregate_validator_public_keys<C: Config>(
+fn validator_public_keys<'s, C: Config>(
     indices: &ValidatorIndexList<C>,
-    state: &BeaconState<C>,
-) -> Result<AggregatePublicKey, Error> {
-    let mut aggr_pkey = AggregatePublicKey::new();
-    for i in indices.iter() {
-        let ind =
-            usize::try_from(*i).expect("Unable to convert ValidatorIndex to usize for indexing");
-        if state.validators.len() <= ind {
-            return Err(Error::IndexOutOfRange);
-        }
-        aggr_pkey.add(&state.validators[ind].pubkey);
-    }
-    Ok(aggr_pkey)
+    state: &'s BeaconState<C>,
+) -> Result<Vec<&'s PublicKey>, Error> {
+    indices
+        .iter()
+        .map(|i| {
+            let ind = usize::try_from(*i)
+                .expect("Unable to convert ValidatorIndex to usize for indexing");
+            state
+                .validators
+                .get(ind)
+                .map(|validator| &validator.pubkey)
+                .ok_or(Error::IndexOutOfRange)
+        })
+        .collect()
 }
 
-pub fn validate_indexed_attestation<C: Config>(
-    state: &BeaconState<C>,
+// Check the attesting indices of ``indexed_attestation`` and look up the public keys they refer to.
+fn attesting_public_keys<'s, C: Config>(
+    state: &'s BeaconState<C>,
     indexed_attestation: &IndexedAttestation<C>,
-) -> Result<(), Error> {
+) -> Result<Vec<&'s PublicKey>, Error> {
     let indices = &indexed_attestation.attesting_indices;
 
     let max_validators = C::MaxValidatorsPerCommittee::to_usize();
@@ -72,43 +74,57 @@ pub fn validate_indexed_attestation<C: Config>(
         return Err(Error::IndicesNotSorted);
     }
 
-    let aggr_pubkey = aggregate_validator_public_keys(indices, state)?;
+    validator_public_keys(indices, state)
+}
 
-    let hash = indexed_attestation.data.tree_hash_root();
+pub fn validate_indexed_attestation<C: Config>(
+    state: &BeaconState<C>,
+    indexed_attestation: &IndexedAttestation<C>,
+) -> Result<(), Error> {
+    let pubkeys = attesting_public_keys(state, indexed_attestation)?;
 
-    if indexed_attestation.signature.verify_multiple(
-        &[hash.as_slice()],
+    if crypto::aggregate_verify(
+        &pubkeys,
+        &crypto::hash_tree_root(&indexed_attestation.data),
+        &indexed_attestation.signature,
         accessors::get_domain(
             state,
             C::domain_attestation(),
             Some(indexed_attestation.data.target.epoch),
         ),
-        &[&aggr_pubkey],
-    ) {
+    )? {
         Ok(())
     } else {
         Err(Error::InvalidSignature)
     }
+}
+
+// Check the parts of ``block``'s header that ``process_block_header`` verifies, without mutating ``state``.
+pub fn verify_block_header<C: Config>(
+    state: &BeaconState<C>,
+    block: &BeaconBlock<C>,
+) -> Result<(), Error> {
+    // Verify that the slots match
+    if block.slot != state.slot {
+        return Err(Error::BlockSlotMismatch);
+    }
 
-    // Check signature
-    // Since bit_1_indices is empty (because of the first `if`) we only check that
-    // let aggr_pubkey1 = aggregate_validator_public_keys(bit_0_indices, state)?;
-
-    // let hash_1 = AttestationDataAndCustodyBit {
-    //     data: indexed_attestation.data.clone(),
-    //     custody_bit: false,
-    // }
-    // .tree_hash_root();
-
-    // if indexed_attestation.signature.verify(
-    //     &hash_1,
-    //     accessors::get_domain(state, 0, Some(indexed_attestation.data.target.epoch)),
-    //     &aggr_pubkey1
-    // ) {
-    //     Ok(())
-    // } else {
-    //     Err(Error::InvalidSignature)
-    // }
+    // Verify that the parent matches
+    if block.parent_root != crypto::hash_tree_root(&state.latest_block_header) {
+        return Err(Error::BlockParentMismatch);
+    }
+
+    // Verify proposer is not slashed
+    let proposer_index = accessors::get_beacon_proposer_index(state)?;
+    let proposer = usize::try_from(proposer_index)
+        .ok()
+        .and_then(|index| state.validators.get(index))
+        .ok_or(Error::IndexOutOfRange)?;
+    if proposer.slashed {
+        return Err(Error::ProposerSlashed);
+    }
+
+    Ok(())
 }
 
 pub fn is_valid_merkle_branch(
@@ -148,8 +164,10 @@ mod tests {
     use bls::{PublicKey, SecretKey};
     //use std::u64::max_value() as epoch_max;
     const EPOCH_MAX: u64 = u64::max_value();
+    use tree_hash::TreeHash;
+    use types::config::MinimalConfig;
     use types::primitives::H256;
-    use types::types::{Checkpoint, Crosslink};
+    use types::types::Checkpoint;
 
     fn default_validator() -> Validator {
         Validator {
@@ -164,16 +182,6 @@ mod tests {
         }
     }
 
-    const fn default_crosslink() -> Crosslink {
-        Crosslink {
-            shard: 0,
-            parent_root: H256([0; 32]),
-            start_epoch: 0,
-            end_epoch: 1,
-            data_root: H256([0; 32]),
-        }
-    }
-
     const fn default_attestation_data() -> AttestationData {
         AttestationData {
             beacon_block_root: H256([0; 32]),
@@ -298,6 +306,22 @@ mod tests {
         assert!(!is_slashable_attestation_data(&data_1, &data_2));
     }
 
+    #[test]
+    fn test_verify_block_header_parent_mismatch() {
+        let state = BeaconState::<MinimalConfig>::default();
+        let block = BeaconBlock::<MinimalConfig> {
+            parent_root: H256([1; 32]),
+            ..BeaconBlock::default()
+        };
+        let state_before = state.clone();
+
+        assert_eq!(
+            verify_block_header(&state, &block),
+            Err(Error::BlockParentMismatch)
+        );
+        assert_eq!(state, state_before);
+    }
+
     fn hash_concat(v1: H256, v2: H256) -> H256 {
         let mut val = v1.as_bytes().to_vec();
         val.append(&mut v2.as_bytes().to_vec());
@@ -337,6 +361,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_valid_merkle_branch_depth_4() {
+        // Deep enough that every index bit, not just the lowest two, selects a side.
+        let mut layers = vec![(0..16_u8).map(|i| H256::from([i; 32])).collect::<Vec<_>>()];
+        for _ in 0..4 {
+            let layer = layers
+                .last()
+                .expect("Expected a layer")
+                .chunks(2)
+                .map(|pair| hash_concat(pair[0], pair[1]))
+                .collect();
+            layers.push(layer);
+        }
+        let root = layers[4][0];
+
+        for index in 0..16 {
+            let branch = (0..4)
+                .map(|height| layers[height][(index >> height) ^ 1])
+                .collect::<Vec<_>>();
+            assert!(
+                is_valid_merkle_branch(&layers[0][index], &branch, 4, index as u64, &root)
+                    .expect("Unexpected error")
+            );
+        }
+    }
+
     #[test]
     fn test_merkle_branch_depth() {
         let leaf_b00 = H256::from([0xAF; 32]);
@@ -403,7 +453,89 @@ mod tests {
     mod validate_indexed_attestation_tests {
         use super::*;
         use bls::{AggregateSignature, SecretKey, Signature};
-        use types::config::MainnetConfig;
+        use types::config::{MainnetConfig, MinimalConfig};
+
+        fn attestation_with_indices<C: Config>(count: usize) -> IndexedAttestation<C> {
+            let mut attestation: IndexedAttestation<C> = IndexedAttestation::default();
+            for index in 0..count {
+                attestation
+                    .attesting_indices
+                    .push(index as u64)
+                    .expect("Unable to add attesting index");
+            }
+            attestation
+        }
+
+        // Every validator has the same key, so the aggregate signature is the same signature
+        // added once per attesting index.
+        fn signed_attestation_with_indices<C: Config>(
+            state: &BeaconState<C>,
+            secret_key: &SecretKey,
+            count: usize,
+        ) -> IndexedAttestation<C> {
+            let mut attestation = attestation_with_indices::<C>(count);
+            let domain = accessors::get_domain(
+                state,
+                C::domain_attestation(),
+                Some(attestation.data.target.epoch),
+            );
+            let signature = Signature::new(
+                attestation.data.tree_hash_root().as_slice(),
+                domain,
+                secret_key,
+            );
+            attestation.signature = AggregateSignature::new();
+            for _ in 0..count {
+                attestation.signature.add(&signature);
+            }
+            attestation
+        }
+
+        // The specification accepts up to and including `MAX_VALIDATORS_PER_COMMITTEE` indices.
+        fn check_committee_size_boundary<C: Config>() {
+            let secret_key = SecretKey::random();
+            let max_validators = C::MaxValidatorsPerCommittee::to_usize();
+            let validator = Validator {
+                pubkey: PublicKey::from_secret_key(&secret_key),
+                ..default_validator()
+            };
+            let state: BeaconState<C> = BeaconState {
+                validators: VariableList::from(vec![validator; max_validators]),
+                ..BeaconState::default()
+            };
+
+            assert_eq!(
+                validate_indexed_attestation(
+                    &state,
+                    &signed_attestation_with_indices(&state, &secret_key, max_validators - 1)
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                validate_indexed_attestation(
+                    &state,
+                    &signed_attestation_with_indices(&state, &secret_key, max_validators)
+                ),
+                Ok(())
+            );
+
+            // An attestation with more indices cannot even be constructed.
+            let mut attestation = attestation_with_indices::<C>(max_validators);
+            assert!(attestation
+                .attesting_indices
+                .push(max_validators as u64)
+                .is_err());
+        }
+
+        #[test]
+        fn committee_size_boundary_mainnet() {
+            check_committee_size_boundary::<MainnetConfig>();
+        }
+
+        #[test]
+        fn committee_size_boundary_minimal() {
+            check_committee_size_boundary::<MinimalConfig>();
+        }
 
         #[test]
         fn index_set_not_sorted() {
@@ -488,6 +620,10 @@ mod tests {
                 .attesting_indices
                 .push(0)
                 .expect("Unable to add attesting index");
+            attestation
+                .attesting_indices
+                .push(1)
+                .expect("Unable to add attesting index");
 
             let skey1 = SecretKey::random();
             let pkey1 = PublicKey::from_secret_key(&skey1);
@@ -541,9 +677,12 @@ mod tests {
 
             attestation.signature = asig;
 
-            let aggr_pubkey =
-                aggregate_validator_public_keys(&attestation.attesting_indices, &state)
-                    .expect("Success");
+            let pubkeys = state
+                .validators
+                .iter()
+                .map(|validator| validator.pubkey.clone())
+                .collect::<Vec<_>>();
+            let aggr_pubkey = crypto::bls_aggregate_pubkeys(&pubkeys);
             assert!(attestation.signature.verify(
                 &digest1,
                 accessors::get_domain(
@@ -556,5 +695,37 @@ mod tests {
 
             assert_eq!(validate_indexed_attestation(&state, &attestation), Ok(()));
         }
+
+        #[test]
+        fn signature_from_wrong_key() {
+            let mut state: BeaconState<MainnetConfig> = BeaconState::default();
+            let mut attestation: IndexedAttestation<MainnetConfig> = IndexedAttestation::default();
+            attestation
+                .attesting_indices
+                .push(0)
+                .expect("Unable to add attesting index");
+            state
+                .validators
+                .push(default_validator())
+                .expect("Expected successfull push to validator collection");
+
+            let domain = accessors::get_domain(
+                &state,
+                MainnetConfig::domain_attestation(),
+                Some(attestation.data.target.epoch),
+            );
+            let signature = Signature::new(
+                attestation.data.tree_hash_root().as_slice(),
+                domain,
+                &SecretKey::random(),
+            );
+            attestation.signature = AggregateSignature::new();
+            attestation.signature.add(&signature);
+
+            assert_eq!(
+                validate_indexed_attestation(&state, &attestation),
+                Err(Error::InvalidSignature)
+            );
+        }
     }
 }
@@ -1,5 +1,6 @@
 use mockall::*;
 use ssz_types::BitList;
+use std::cmp::max;
 use types::{
     beacon_state::BeaconState,
     config::Config,
@@ -91,8 +92,13 @@ pub fn get_total_balance<C: Config>(
 }
 
 // ok
-pub fn get_total_active_balance<C: Config>(_state: &BeaconState<C>) -> Result<u64, Error> {
-    Ok(1)
+pub fn get_total_active_balance<C: Config>(state: &BeaconState<C>) -> Result<u64, Error> {
+    let active_validator_indices = get_active_validator_indices(state, get_current_epoch(state))
+        .copied()
+        .collect::<Vec<_>>();
+    let total_balance = get_total_balance(state, &active_validator_indices)?;
+    // Keeps reward calculations well-defined when no validators are active.
+    Ok(max(total_balance, C::effective_balance_increment()))
 }
 
 // ok
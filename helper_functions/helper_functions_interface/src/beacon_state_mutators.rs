@@ -2,15 +2,22 @@ use types::beacon_state::BeaconState;
 use types::config::Config;
 use types::helper_functions_types::Error;
 use types::primitives::{Gwei, ValidatorIndex};
-use types::types::Validator;
 
 // ok
-pub fn increase_balance(_validator: &mut Validator, _delta: Gwei) -> Result<(), Error> {
+pub fn increase_balance<C: Config>(
+    _state: &mut BeaconState<C>,
+    _index: ValidatorIndex,
+    _delta: Gwei,
+) -> Result<(), Error> {
     Ok(())
 }
 
 // ok
-pub fn decrease_balance(_validator: &mut Validator, _delta: Gwei) -> Result<(), Error> {
+pub fn decrease_balance<C: Config>(
+    _state: &mut BeaconState<C>,
+    _index: ValidatorIndex,
+    _delta: Gwei,
+) -> Result<(), Error> {
     Ok(())
 }
 
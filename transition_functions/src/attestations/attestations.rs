@@ -119,21 +119,35 @@ where
 mod attestations_tests {
     use crate::attestations::attestations::AttestableBlock;
     use ssz_types::{BitList, FixedVector, VariableList};
+    use typenum::Unsigned as _;
     use types::{
         beacon_state::BeaconState,
-        config::{Config, MainnetConfig},
+        config::{Config, MainnetConfig, MinimalConfig},
         primitives::{Epoch, Gwei, ValidatorIndex},
         types::PendingAttestation,
     };
 
+    #[test]
+    fn test_empty_pending_attestation_capacity() {
+        let mainnet = PendingAttestation::<MainnetConfig>::empty();
+        assert_eq!(
+            mainnet.aggregation_bits.len(),
+            <MainnetConfig as Config>::MaxValidatorsPerCommittee::to_usize()
+        );
+
+        let minimal = PendingAttestation::<MinimalConfig>::empty();
+        assert_eq!(
+            minimal.aggregation_bits.len(),
+            <MinimalConfig as Config>::MaxValidatorsPerCommittee::to_usize()
+        );
+    }
+
     #[test]
     fn test_get_matching_source_attestations_1() {
         let mut bs: BeaconState<MainnetConfig> = BeaconState {
             ..BeaconState::default()
         };
-        let mut pa: PendingAttestation<MainnetConfig> = PendingAttestation {
-            ..PendingAttestation::default()
-        };
+        let mut pa: PendingAttestation<MainnetConfig> = PendingAttestation::empty();
         bs.slot = 0;
         bs.current_epoch_attestations.push(pa);
         let result = bs.get_matching_source_attestations(0);
@@ -145,9 +159,7 @@ mod attestations_tests {
         let mut bs: BeaconState<MainnetConfig> = BeaconState {
             ..BeaconState::default()
         };
-        let mut pa: PendingAttestation<MainnetConfig> = PendingAttestation {
-            ..PendingAttestation::default()
-        };
+        let mut pa: PendingAttestation<MainnetConfig> = PendingAttestation::empty();
         bs.slot = 32;
         bs.current_epoch_attestations.push(pa);
 
@@ -161,9 +173,7 @@ mod attestations_tests {
     //     let mut bs: BeaconState<MainnetConfig> = BeaconState {
     //         ..BeaconState::default()
     //     };
-    //     let mut pa: PendingAttestation<MainnetConfig> = PendingAttestation {
-    //         ..PendingAttestation::default()
-    //     };
+    //     let mut pa: PendingAttestation<MainnetConfig> = PendingAttestation::empty();
     //     bs.slot = 1;
     //     bs.current_epoch_attestations.push(pa);
 
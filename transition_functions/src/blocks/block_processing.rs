@@ -1,11 +1,13 @@
+use crate::error::TransitionError;
 use helper_functions::beacon_state_accessors::*;
 use helper_functions::beacon_state_mutators::*;
+use helper_functions::committee_cache::CommitteeCache;
 use helper_functions::crypto::{bls_verify, hash, hash_tree_root, signed_root};
 use helper_functions::math::*;
 use helper_functions::misc::{compute_domain, compute_epoch_at_slot};
 use helper_functions::predicates::{
     is_active_validator, is_slashable_attestation_data, is_slashable_validator,
-    is_valid_merkle_branch, validate_indexed_attestation,
+    is_valid_merkle_branch, validate_indexed_attestation, verify_block_header,
 };
 use std::collections::BTreeSet;
 use std::convert::TryInto;
@@ -19,68 +21,100 @@ use types::{
     primitives::H256,
     types::{
         Attestation, AttestationData, AttesterSlashing, BeaconBlock, BeaconBlockBody,
-        BeaconBlockHeader, Deposit, PendingAttestation, ProposerSlashing, Validator, VoluntaryExit,
+        BeaconBlockHeader, Deposit, PendingAttestation, ProposerSlashing, SignedBeaconBlock,
+        SignedVoluntaryExit, Validator,
     },
 };
 
-pub fn process_block<T: Config>(state: &mut BeaconState<T>, block: &BeaconBlock<T>) {
-    process_block_header(state, &block);
-    process_randao(state, &block.body);
+pub fn process_block<T: Config>(
+    state: &mut BeaconState<T>,
+    block: &BeaconBlock<T>,
+) -> Result<(), TransitionError> {
+    process_block_header(state, &block)?;
+    process_randao(state, &block.body)?;
     process_eth1_data(state, &block.body);
-    process_operations(state, &block.body);
+    process_operations(state, &block.body)
 }
 
-fn process_voluntary_exit<T: Config>(state: &mut BeaconState<T>, exit: &VoluntaryExit) {
-    let validator = &state.validators[exit.validator_index as usize];
+fn process_voluntary_exit<T: Config>(
+    state: &mut BeaconState<T>,
+    signed_exit: &SignedVoluntaryExit,
+) -> Result<(), TransitionError> {
+    let exit = &signed_exit.message;
+    let index = exit.validator_index;
+    let validator = state
+        .validators
+        .get(index as usize)
+        .ok_or(TransitionError::ValidatorIndexOutOfRange { index })?;
     // Verify the validator is active
-    assert!(is_active_validator(&validator, get_current_epoch(state)));
+    if !is_active_validator(&validator, get_current_epoch(state)) {
+        return Err(TransitionError::ValidatorNotActive { index });
+    }
     // Verify the validator has not yet exited
-    assert!(validator.exit_epoch == FAR_FUTURE_EPOCH);
+    if validator.exit_epoch != FAR_FUTURE_EPOCH {
+        return Err(TransitionError::ValidatorAlreadyExited { index });
+    }
     // Exits must specify an epoch when they become valid; they are not valid before then
-    assert!(get_current_epoch(state) >= exit.epoch);
+    if get_current_epoch(state) < exit.epoch {
+        return Err(TransitionError::VoluntaryExitTooEarly { epoch: exit.epoch });
+    }
     // Verify the validator has been active long enough
-    assert!(
-        get_current_epoch(state) >= validator.activation_epoch + T::persistent_committee_period()
-    );
+    if get_current_epoch(state) < validator.activation_epoch + T::persistent_committee_period() {
+        return Err(TransitionError::ValidatorTooYoung { index });
+    }
     // Verify signature
     let domain = get_domain(state, T::domain_voluntary_exit() as u32, Some(exit.epoch));
-    assert!(bls_verify(
-        &(bls::PublicKeyBytes::from_bytes(&validator.pubkey.as_bytes()).unwrap()),
-        signed_root(exit).as_bytes(),
-        &(exit.signature.clone()).try_into().unwrap(),
-        domain
+    if !bls_verify(
+        &public_key_bytes(&validator.pubkey)?,
+        hash_tree_root(exit).as_bytes(),
+        &signature_bytes(&signed_exit.signature)?,
+        domain,
     )
-    .unwrap());
+    .unwrap_or(false)
+    {
+        return Err(TransitionError::InvalidVoluntaryExitSignature);
+    }
     // Initiate exit
-    initiate_validator_exit(state, exit.validator_index).unwrap();
+    initiate_validator_exit(state, index)?;
+    Ok(())
 }
 
-fn process_deposit<T: Config>(state: &mut BeaconState<T>, deposit: &Deposit) {
+pub(crate) fn process_deposit<T: Config>(
+    state: &mut BeaconState<T>,
+    deposit: &Deposit,
+) -> Result<(), TransitionError> {
     //# Verify the Merkle branch  is_valid_merkle_branch
 
-    assert!(is_valid_merkle_branch(
+    if !is_valid_merkle_branch(
         &hash_tree_root(&deposit.data),
         &deposit.proof,
         DEPOSIT_CONTRACT_TREE_DEPTH + 1,
         state.eth1_deposit_index,
-        &state.eth1_data.deposit_root
-    )
-    .unwrap());
+        &state.eth1_data.deposit_root,
+    )? {
+        return Err(TransitionError::InvalidDepositProof);
+    }
 
     //# Deposits must be processed in order
     state.eth1_deposit_index += 1;
 
-    let pubkey = (&deposit.data.pubkey).try_into().unwrap();
-    // let pubkey = bls::PublicKey::from_bytes(&deposit.data.pubkey.clone().as_bytes()).unwrap();
     let amount = deposit.data.amount;
 
-    for (index, validator) in state.validators.iter_mut().enumerate() {
-        // if bls::PublicKeyBytes::from_bytes(&v.pubkey.as_bytes()).unwrap() == *pubkey {
-        if validator.pubkey == pubkey {
-            //# Increase balance by deposit amount
-            increase_balance(state, index as u64, amount).unwrap();
-            return;
-        }
+    // The deposit contract does not validate public keys. A deposit with an invalid one cannot
+    // have a valid signature either, so it is skipped like one with an invalid signature.
+    let pubkey: bls::PublicKey = match (&deposit.data.pubkey).try_into() {
+        Ok(pubkey) => pubkey,
+        Err(_) => return Ok(()),
+    };
+
+    if let Some(index) = state
+        .validators
+        .iter()
+        .position(|validator| validator.pubkey == pubkey)
+    {
+        //# Increase balance by deposit amount
+        increase_balance(state, index as u64, amount)?;
+        return Ok(());
     }
     //# Verify the deposit signature (proof of possession) for new validators.
     //# Note: The deposit contract does not check signatures.
@@ -88,22 +122,21 @@ fn process_deposit<T: Config>(state: &mut BeaconState<T>, deposit: &Deposit) {
     let domain = compute_domain(T::domain_deposit() as u32, None);
 
     if !bls_verify(
-        &pubkey.clone().try_into().unwrap(),
+        &deposit.data.pubkey,
         signed_root(&deposit.data).as_bytes(),
         &deposit.data.signature,
         domain,
     )
-    .unwrap()
+    .unwrap_or(false)
     {
-        return;
+        return Ok(());
     }
 
     //# Add validator and balance entries
-    // bls::PublicKey::from_bytes(&pubkey.as_bytes()).unwrap()
     state
         .validators
         .push(Validator {
-            pubkey: bls::PublicKey::from_bytes(&pubkey.as_bytes()).unwrap(),
+            pubkey,
             withdrawal_credentials: deposit.data.withdrawal_credentials,
             activation_eligibility_epoch: FAR_FUTURE_EPOCH,
             activation_epoch: FAR_FUTURE_EPOCH,
@@ -117,13 +150,15 @@ fn process_deposit<T: Config>(state: &mut BeaconState<T>, deposit: &Deposit) {
         })
         .unwrap();
     &state.balances.push(amount);
+    Ok(())
 }
 
-fn process_block_header<T: Config>(state: &mut BeaconState<T>, block: &BeaconBlock<T>) {
-    //# Verify that the slots match
-    assert!(block.slot == state.slot);
-    //# Verify that the parent matches
-    assert!(block.parent_root == signed_root(&state.latest_block_header));
+fn process_block_header<T: Config>(
+    state: &mut BeaconState<T>,
+    block: &BeaconBlock<T>,
+) -> Result<(), TransitionError> {
+    //# Verify that the slots match, the parent matches and the proposer is not slashed
+    verify_block_header(state, block)?;
     //# Save current block as the new latest block
     state.latest_block_header = BeaconBlockHeader {
         slot: block.slot,
@@ -131,106 +166,117 @@ fn process_block_header<T: Config>(state: &mut BeaconState<T>, block: &BeaconBlo
         //# `state_root` is zeroed and overwritten in the next `process_slot` call
         body_root: hash_tree_root(&block.body),
         state_root: H256::from_low_u64_be(0),
-        ..BeaconBlockHeader::default()
     };
-    //# Verify proposer is not slashed
-    let proposer = &state.validators[get_beacon_proposer_index(&state).unwrap() as usize];
-    assert!(!proposer.slashed);
-    //# Verify proposer signature
-    println!("{}", bls_verify(
-        &bls::PublicKeyBytes::from_bytes(&proposer.pubkey.as_bytes()).unwrap(),
-        signed_root(block).as_bytes(),
-        &block.signature.clone().try_into().unwrap(),
-        get_domain(&state, T::domain_beacon_proposer() as u32, None)
-    )
+    Ok(())
+}
 
-    if !cfg!(test) {
-    assert!(bls_verify(
-        &bls::PublicKeyBytes::from_bytes(&proposer.pubkey.as_bytes()).unwrap(),
-        signed_root(block).as_bytes(),
-        &block.signature.clone().try_into().unwrap(),
-        get_domain(&state, T::domain_beacon_proposer() as u32, None)
+/// Verifies the proposer signature of `signed_block` against `state`, which must already be at
+/// the slot of the block.
+pub fn verify_block_signature<T: Config>(
+    state: &BeaconState<T>,
+    signed_block: &SignedBeaconBlock<T>,
+) -> Result<(), TransitionError> {
+    let proposer = &state.validators[get_beacon_proposer_index(&state)? as usize];
+    if !bls_verify(
+        &public_key_bytes(&proposer.pubkey)?,
+        hash_tree_root(&signed_block.message).as_bytes(),
+        &signature_bytes(&signed_block.signature)?,
+        get_domain(&state, T::domain_beacon_proposer() as u32, None),
     )
-    .unwrap());
-}
+    .unwrap_or(false)
+    {
+        return Err(TransitionError::InvalidProposerSignature);
+    }
+    Ok(())
 }
 
-fn process_randao<T: Config>(state: &mut BeaconState<T>, body: &BeaconBlockBody<T>) {
+fn process_randao<T: Config>(
+    state: &mut BeaconState<T>,
+    body: &BeaconBlockBody<T>,
+) -> Result<(), TransitionError> {
     let epoch = get_current_epoch(&state);
     //# Verify RANDAO reveal
-    let proposer = &state.validators[get_beacon_proposer_index(&state).unwrap() as usize];
-    assert!(bls_verify(
-        &(proposer.pubkey.clone()).try_into().unwrap(),
+    let proposer = &state.validators[get_beacon_proposer_index(&state)? as usize];
+    if !bls_verify(
+        &public_key_bytes(&proposer.pubkey)?,
         hash_tree_root(&epoch).as_bytes(),
-        &(body.randao_reveal.clone()).try_into().unwrap(),
-        get_domain(&state, T::domain_randao() as u32, None)
+        &signature_bytes(&body.randao_reveal)?,
+        get_domain(&state, T::domain_randao() as u32, None),
     )
-    .unwrap());
+    .unwrap_or(false)
+    {
+        return Err(TransitionError::InvalidRandaoReveal);
+    }
     //# Mix in RANDAO reveal
     let mix = xor(
-        get_randao_mix(&state, epoch).unwrap().as_fixed_bytes(),
-        &hash(&body.randao_reveal.as_bytes())
-            .as_slice()
-            .try_into()
-            .unwrap(),
+        get_randao_mix(&state, epoch)?.as_fixed_bytes(),
+        H256::from_slice(&hash(&body.randao_reveal.as_bytes())).as_fixed_bytes(),
     );
-    let mut array = [0; 32];
-    let mix = &mix[..array.len()]; // panics if not enough data
-    array.copy_from_slice(mix);
-    state.randao_mixes[(epoch % T::EpochsPerHistoricalVector::U64) as usize] =
-        array.try_into().unwrap();
+    state.randao_mixes[(epoch % T::EpochsPerHistoricalVector::U64) as usize] = H256::from(mix);
+    Ok(())
 }
 
 fn process_proposer_slashing<T: Config>(
     state: &mut BeaconState<T>,
     proposer_slashing: &ProposerSlashing,
-) {
-    let proposer = &state.validators[proposer_slashing.proposer_index as usize];
+) -> Result<(), TransitionError> {
+    let index = proposer_slashing.proposer_index;
+    let proposer = state
+        .validators
+        .get(index as usize)
+        .ok_or(TransitionError::ValidatorIndexOutOfRange { index })?;
     // Verify slots match
-    assert_eq!(
-        proposer_slashing.header_1.slot,
-        proposer_slashing.header_2.slot
-    );
+    if proposer_slashing.signed_header_1.message.slot
+        != proposer_slashing.signed_header_2.message.slot
+    {
+        return Err(TransitionError::ProposerSlashingSlotMismatch);
+    }
     // But the headers are different
-    assert_ne!(proposer_slashing.header_1, proposer_slashing.header_2);
+    if proposer_slashing.signed_header_1 == proposer_slashing.signed_header_2 {
+        return Err(TransitionError::ProposerSlashingIdenticalHeaders);
+    }
     // Check proposer is slashable
-    assert!(is_slashable_validator(&proposer, get_current_epoch(state)));
+    if !is_slashable_validator(&proposer, get_current_epoch(state)) {
+        return Err(TransitionError::ValidatorNotSlashable { index });
+    }
     // Signatures are valid
-    let headers: [BeaconBlockHeader; 2] = [
-        proposer_slashing.header_1.clone(),
-        proposer_slashing.header_2.clone(),
+    let signed_headers = [
+        &proposer_slashing.signed_header_1,
+        &proposer_slashing.signed_header_2,
     ];
-    for header in &headers {
+    for signed_header in &signed_headers {
         let domain = get_domain(
             state,
             T::domain_beacon_proposer() as u32,
-            Some(compute_epoch_at_slot::<T>(header.slot)),
+            Some(compute_epoch_at_slot::<T>(signed_header.message.slot)),
         );
-        //# Sekanti eilutė tai ******* amazing. signed_root helperiuose užkomentuota
-        assert!(bls_verify(
-            &(proposer.pubkey.clone()).try_into().unwrap(),
-            signed_root(header).as_bytes(),
-            &(header.signature.clone()).try_into().unwrap(),
-            domain
+        if !bls_verify(
+            &public_key_bytes(&proposer.pubkey)?,
+            hash_tree_root(&signed_header.message).as_bytes(),
+            &signature_bytes(&signed_header.signature)?,
+            domain,
         )
-        .unwrap());
+        .unwrap_or(false)
+        {
+            return Err(TransitionError::InvalidProposerSlashingSignature);
+        }
     }
 
-    slash_validator(state, proposer_slashing.proposer_index, None).unwrap();
+    slash_validator(state, index, None)?;
+    Ok(())
 }
 
 fn process_attester_slashing<T: Config>(
     state: &mut BeaconState<T>,
     attester_slashing: &AttesterSlashing<T>,
-) {
+) -> Result<(), TransitionError> {
     let attestation_1 = &attester_slashing.attestation_1;
     let attestation_2 = &attester_slashing.attestation_2;
-    assert!(is_slashable_attestation_data(
-        &attestation_1.data,
-        &attestation_2.data
-    ));
-    assert!(validate_indexed_attestation(state, &attestation_1).is_ok());
-    assert!(validate_indexed_attestation(state, &attestation_2).is_ok());
+    if !is_slashable_attestation_data(&attestation_1.data, &attestation_2.data) {
+        return Err(TransitionError::AttestationDataNotSlashable);
+    }
+    validate_indexed_attestation(state, &attestation_1)?;
+    validate_indexed_attestation(state, &attestation_2)?;
 
     let mut slashed_any = false;
 
@@ -252,44 +298,70 @@ fn process_attester_slashing<T: Config>(
         let validator = &state.validators[index as usize];
 
         if is_slashable_validator(&validator, get_current_epoch(state)) {
-            slash_validator(state, index, None).unwrap();
+            slash_validator(state, index, None)?;
             slashed_any = true;
         }
     }
-    assert!(slashed_any);
+    if !slashed_any {
+        return Err(TransitionError::NoValidatorsSlashed);
+    }
+    Ok(())
 }
 
-fn process_attestation<T: Config>(state: &mut BeaconState<T>, attestation: &Attestation<T>) {
+fn process_attestation<T: Config>(
+    state: &mut BeaconState<T>,
+    attestation: &Attestation<T>,
+    committee_cache: &mut CommitteeCache,
+) -> Result<(), TransitionError> {
     let data = &attestation.data;
     let attestation_slot = data.slot;
-    assert!(data.index < get_committee_count_at_slot(state, attestation_slot).unwrap()); //# Nėra index ir slot. ¯\_(ツ)_/¯
-    assert!(
-        data.target.epoch == get_previous_epoch(state)
-            || data.target.epoch == get_current_epoch(state)
-    );
-    assert!(
-        attestation_slot + T::min_attestation_inclusion_delay() <= state.slot
-            && state.slot <= attestation_slot + T::SlotsPerEpoch::U64
-    );
+    let committee_count = get_committee_count_at_slot(state, attestation_slot)?;
+    if data.index >= committee_count {
+        return Err(TransitionError::CommitteeIndexOutOfRange {
+            index: data.index,
+            count: committee_count,
+        });
+    }
+    if data.target.epoch != get_previous_epoch(state)
+        && data.target.epoch != get_current_epoch(state)
+    {
+        return Err(TransitionError::TargetEpochOutOfRange {
+            epoch: data.target.epoch,
+        });
+    }
+    if !(attestation_slot + T::min_attestation_inclusion_delay() <= state.slot
+        && state.slot <= attestation_slot + T::SlotsPerEpoch::U64)
+    {
+        return Err(TransitionError::AttestationOutsideInclusionWindow);
+    }
 
-    let committee = get_beacon_committee(state, attestation_slot, data.index).unwrap();
-    assert_eq!(attestation.aggregation_bits.len(), committee.len());
+    let committee = committee_cache.get_beacon_committee(state, attestation_slot, data.index)?;
+    if attestation.aggregation_bits.len() != committee.len() {
+        return Err(TransitionError::AggregationBitsLengthMismatch {
+            bits: attestation.aggregation_bits.len(),
+            committee_size: committee.len(),
+        });
+    }
 
     let pending_attestation = PendingAttestation {
         data: attestation.data.clone(),
         aggregation_bits: attestation.aggregation_bits.clone(),
         inclusion_delay: (state.slot - attestation_slot) as u64,
-        proposer_index: get_beacon_proposer_index(state).unwrap(),
+        proposer_index: get_beacon_proposer_index(state)?,
     };
 
     if data.target.epoch == get_current_epoch(state) {
-        assert_eq!(data.source, state.current_justified_checkpoint);
+        if data.source != state.current_justified_checkpoint {
+            return Err(TransitionError::SourceMismatch);
+        }
         state
             .current_epoch_attestations
             .push(pending_attestation)
             .unwrap();
     } else {
-        assert_eq!(data.source, state.previous_justified_checkpoint);
+        if data.source != state.previous_justified_checkpoint {
+            return Err(TransitionError::SourceMismatch);
+        }
         state
             .previous_epoch_attestations
             .push(pending_attestation)
@@ -297,11 +369,19 @@ fn process_attestation<T: Config>(state: &mut BeaconState<T>, attestation: &Atte
     }
 
     //# Check signature
-    assert!(validate_indexed_attestation(
-        &state,
-        &get_indexed_attestation(&state, &attestation).unwrap()
-    )
-    .is_ok());
+    let indexed_attestation = committee_cache.get_indexed_attestation(&state, &attestation)?;
+    validate_indexed_attestation(&state, &indexed_attestation)?;
+    Ok(())
+}
+
+fn public_key_bytes(public_key: &bls::PublicKey) -> Result<bls::PublicKeyBytes, TransitionError> {
+    bls::PublicKeyBytes::from_bytes(&public_key.as_bytes())
+        .map_err(|_| TransitionError::InvalidPublicKey)
+}
+
+fn signature_bytes(signature: &bls::Signature) -> Result<bls::SignatureBytes, TransitionError> {
+    bls::SignatureBytes::from_bytes(&signature.as_bytes())
+        .map_err(|_| TransitionError::InvalidSignatureBytes)
 }
 
 fn process_eth1_data<T: Config>(state: &mut BeaconState<T>, body: &BeaconBlockBody<T>) {
@@ -317,45 +397,62 @@ fn process_eth1_data<T: Config>(state: &mut BeaconState<T>, body: &BeaconBlockBo
     }
 }
 
-fn process_operations<T: Config>(state: &mut BeaconState<T>, body: &BeaconBlockBody<T>) {
+fn process_operations<T: Config>(
+    state: &mut BeaconState<T>,
+    body: &BeaconBlockBody<T>,
+) -> Result<(), TransitionError> {
     //# Verify that outstanding deposits are processed up to the maximum number of deposits
-    assert_eq!(
-        body.deposits.len(),
-        std::cmp::min(
-            T::MaxDeposits::USIZE,
-            (state.eth1_data.deposit_count - state.eth1_deposit_index) as usize
-        )
+    // `eth1_data` is voted in by proposers, so `deposit_count` may be lower than the index of
+    // deposits already processed.
+    let expected_deposits = std::cmp::min(
+        T::MaxDeposits::USIZE,
+        state
+            .eth1_data
+            .deposit_count
+            .saturating_sub(state.eth1_deposit_index) as usize,
     );
+    if body.deposits.len() != expected_deposits {
+        return Err(TransitionError::DepositCountMismatch {
+            expected: expected_deposits,
+            actual: body.deposits.len(),
+        });
+    }
 
     for proposer_slashing in body.proposer_slashings.iter() {
-        process_proposer_slashing(state, proposer_slashing);
+        process_proposer_slashing(state, proposer_slashing)?;
     }
     for attester_slashing in body.attester_slashings.iter() {
-        process_attester_slashing(state, attester_slashing);
+        process_attester_slashing(state, attester_slashing)?;
     }
+    let mut committee_cache = CommitteeCache::new();
     for attestation in body.attestations.iter() {
-        process_attestation(state, attestation);
+        process_attestation(state, attestation, &mut committee_cache)?;
     }
     for deposit in body.deposits.iter() {
-        process_deposit(state, deposit);
+        process_deposit(state, deposit)?;
     }
     for voluntary_exit in body.voluntary_exits.iter() {
-        process_voluntary_exit(state, voluntary_exit);
+        process_voluntary_exit(state, voluntary_exit)?;
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod block_processing_tests {
     // use crate::{config::*};
     use super::*;
-    use bls::{PublicKey, SecretKey};
+    use bls::{AggregateSignature, PublicKey, SecretKey, Signature};
     use ethereum_types::H256;
+    use ssz_types::BitList;
     use ssz_types::FixedVector;
     use ssz_types::VariableList;
     use std::iter;
+    use tree_hash::TreeHash;
+    use typenum::Unsigned as _;
     use types::{
-        config::MainnetConfig,
-        types::{BeaconBlock, BeaconBlockHeader},
+        config::{MainnetConfig, MinimalConfig},
+        primitives::Epoch,
+        types::{BeaconBlock, BeaconBlockBody, BeaconBlockHeader, Fork},
     };
 
     const EPOCH_MAX: u64 = u64::max_value();
@@ -396,12 +493,12 @@ mod block_processing_tests {
 
         let block: BeaconBlock<MainnetConfig> = BeaconBlock {
             slot: 0,
-            parent_root: signed_root(&bs.latest_block_header),
+            parent_root: hash_tree_root(&bs.latest_block_header),
             ..BeaconBlock::default()
         };
 
         // execution
-        process_block_header(&mut bs, &block);
+        process_block_header(&mut bs, &block).expect("block header should be valid");
 
         // checks
         assert_eq!(bs.latest_block_header.slot, block.slot);
@@ -412,4 +509,250 @@ mod block_processing_tests {
         );
         assert_eq!(bs.latest_block_header.state_root, block.state_root);
     }
+
+    #[test]
+    fn process_block_header_commits_to_graffiti() {
+        let mut bs: BeaconState<MainnetConfig> = BeaconState {
+            validators: VariableList::from(vec![default_validator()]),
+            ..BeaconState::default()
+        };
+        let block: BeaconBlock<MainnetConfig> = BeaconBlock {
+            parent_root: hash_tree_root(&bs.latest_block_header),
+            body: BeaconBlockBody {
+                graffiti: [0xab; 32],
+                ..BeaconBlockBody::default()
+            },
+            ..BeaconBlock::default()
+        };
+
+        process_block_header(&mut bs, &block).expect("block header should be valid");
+
+        assert_eq!(
+            bs.latest_block_header.body_root,
+            hash_tree_root(&block.body)
+        );
+        assert_ne!(
+            bs.latest_block_header.body_root,
+            hash_tree_root(&BeaconBlockBody::<MainnetConfig>::default())
+        );
+    }
+
+    #[test]
+    fn process_block_header_slot_mismatch() {
+        let mut bs: BeaconState<MainnetConfig> = BeaconState {
+            slot: 1,
+            validators: VariableList::from(vec![default_validator()]),
+            ..BeaconState::default()
+        };
+        let block: BeaconBlock<MainnetConfig> = BeaconBlock {
+            slot: 0,
+            parent_root: hash_tree_root(&bs.latest_block_header),
+            ..BeaconBlock::default()
+        };
+        let latest_block_header = bs.latest_block_header.clone();
+
+        assert_eq!(
+            process_block_header(&mut bs, &block),
+            Err(TransitionError::SlotMismatch)
+        );
+        assert_eq!(bs.latest_block_header, latest_block_header);
+    }
+
+    // Processes an exit from a validator that has been active long enough, signed with the domain
+    // for `signing_version`. The state is at the first epoch of a fork.
+    fn process_exit_signed_with_version(
+        exit_epoch: Epoch,
+        signing_version: [u8; 4],
+    ) -> (BeaconState<MinimalConfig>, Result<(), TransitionError>) {
+        let secret_key = SecretKey::random();
+        let fork_epoch = MinimalConfig::persistent_committee_period() + 1;
+        let mut state: BeaconState<MinimalConfig> = BeaconState {
+            slot: fork_epoch * <MinimalConfig as Config>::SlotsPerEpoch::U64,
+            fork: Fork {
+                previous_version: [0, 0, 0, 1],
+                current_version: [0, 0, 0, 2],
+                epoch: fork_epoch,
+            },
+            validators: VariableList::from(vec![Validator {
+                pubkey: PublicKey::from_secret_key(&secret_key),
+                effective_balance: MinimalConfig::max_effective_balance(),
+                ..default_validator()
+            }]),
+            balances: VariableList::from(vec![MinimalConfig::max_effective_balance()]),
+            ..BeaconState::default()
+        };
+        let exit = VoluntaryExit {
+            epoch: exit_epoch,
+            validator_index: 0,
+        };
+        let domain = compute_domain(
+            MinimalConfig::domain_voluntary_exit() as u32,
+            Some(&signing_version),
+        );
+        let signed_exit = SignedVoluntaryExit {
+            signature: Signature::new(hash_tree_root(&exit).as_bytes(), domain, &secret_key),
+            message: exit,
+        };
+
+        let result = process_voluntary_exit(&mut state, &signed_exit);
+        (state, result)
+    }
+
+    #[test]
+    fn voluntary_exit_from_previous_fork_uses_previous_version() {
+        let fork_epoch = MinimalConfig::persistent_committee_period() + 1;
+
+        let (state, result) = process_exit_signed_with_version(fork_epoch - 1, [0, 0, 0, 1]);
+        assert_eq!(result, Ok(()));
+        assert_ne!(state.validators[0].exit_epoch, FAR_FUTURE_EPOCH);
+
+        let (state, result) = process_exit_signed_with_version(fork_epoch - 1, [0, 0, 0, 2]);
+        assert_eq!(result, Err(TransitionError::InvalidVoluntaryExitSignature));
+        assert_eq!(state.validators[0].exit_epoch, FAR_FUTURE_EPOCH);
+    }
+
+    #[test]
+    fn voluntary_exit_from_current_fork_uses_current_version() {
+        let fork_epoch = MinimalConfig::persistent_committee_period() + 1;
+
+        let (_, result) = process_exit_signed_with_version(fork_epoch, [0, 0, 0, 2]);
+        assert_eq!(result, Ok(()));
+
+        let (_, result) = process_exit_signed_with_version(fork_epoch, [0, 0, 0, 1]);
+        assert_eq!(result, Err(TransitionError::InvalidVoluntaryExitSignature));
+    }
+
+    #[test]
+    fn voluntary_exit_from_unknown_validator() {
+        let mut state: BeaconState<MinimalConfig> = BeaconState {
+            validators: VariableList::from(vec![default_validator()]),
+            ..BeaconState::default()
+        };
+        let signed_exit = SignedVoluntaryExit {
+            message: VoluntaryExit {
+                epoch: 0,
+                validator_index: 1,
+            },
+            signature: Signature::empty_signature(),
+        };
+
+        assert_eq!(
+            process_voluntary_exit(&mut state, &signed_exit),
+            Err(TransitionError::ValidatorIndexOutOfRange { index: 1 })
+        );
+    }
+
+    #[test]
+    fn proposer_slashing_of_unknown_validator() {
+        let mut state: BeaconState<MinimalConfig> = BeaconState::default();
+        let proposer_slashing = ProposerSlashing {
+            proposer_index: 0,
+            signed_header_1: SignedBeaconBlockHeader::default(),
+            signed_header_2: SignedBeaconBlockHeader::default(),
+        };
+
+        assert_eq!(
+            process_proposer_slashing(&mut state, &proposer_slashing),
+            Err(TransitionError::ValidatorIndexOutOfRange { index: 0 })
+        );
+    }
+
+    #[test]
+    fn operations_with_deposit_count_below_deposit_index() {
+        let mut state: BeaconState<MinimalConfig> = BeaconState {
+            eth1_deposit_index: 2,
+            ..BeaconState::default()
+        };
+        state.eth1_data.deposit_count = 1;
+
+        assert_eq!(
+            process_operations(&mut state, &BeaconBlockBody::default()),
+            Ok(())
+        );
+    }
+
+    // A state at slot 1 with enough active validators for two committees per slot.
+    fn state_with_two_committees_per_slot() -> (BeaconState<MinimalConfig>, Vec<SecretKey>) {
+        let secret_keys = (0..64).map(|_| SecretKey::random()).collect::<Vec<_>>();
+        let validators = secret_keys
+            .iter()
+            .map(|secret_key| Validator {
+                pubkey: PublicKey::from_secret_key(secret_key),
+                effective_balance: MinimalConfig::max_effective_balance(),
+                ..default_validator()
+            })
+            .collect::<Vec<_>>();
+        let state = BeaconState {
+            slot: 1,
+            validators: VariableList::from(validators),
+            ..BeaconState::default()
+        };
+        (state, secret_keys)
+    }
+
+    // An attestation for slot 0 signed by every member of committee `index`.
+    fn attestation_signed_by_committee(
+        state: &BeaconState<MinimalConfig>,
+        secret_keys: &[SecretKey],
+        index: u64,
+    ) -> Attestation<MinimalConfig> {
+        let data = AttestationData {
+            slot: 0,
+            index,
+            ..AttestationData::default()
+        };
+        let committee = get_beacon_committee(state, 0, index).expect("committee should exist");
+        let domain = get_domain(
+            state,
+            MinimalConfig::domain_attestation(),
+            Some(data.target.epoch),
+        );
+        let mut aggregation_bits = BitList::with_capacity(committee.len()).unwrap();
+        let mut signature = AggregateSignature::new();
+        for (position, validator_index) in committee.into_iter().enumerate() {
+            aggregation_bits.set(position, true).unwrap();
+            signature.add(&Signature::new(
+                data.tree_hash_root().as_slice(),
+                domain,
+                &secret_keys[validator_index as usize],
+            ));
+        }
+        Attestation {
+            aggregation_bits,
+            data,
+            signature,
+        }
+    }
+
+    #[test]
+    fn process_attestation_with_last_committee_index() {
+        let (mut state, secret_keys) = state_with_two_committees_per_slot();
+        assert_eq!(get_committee_count_at_slot(&state, 0), Ok(2));
+        let attestation = attestation_signed_by_committee(&state, &secret_keys, 1);
+
+        process_attestation(&mut state, &attestation, &mut CommitteeCache::new())
+            .expect("attestation should be valid");
+
+        assert_eq!(state.current_epoch_attestations.len(), 1);
+    }
+
+    #[test]
+    fn process_attestation_with_committee_index_equal_to_count() {
+        let (mut state, _) = state_with_two_committees_per_slot();
+        let attestation = Attestation {
+            aggregation_bits: BitList::with_capacity(4).unwrap(),
+            data: AttestationData {
+                slot: 0,
+                index: 2,
+                ..AttestationData::default()
+            },
+            signature: AggregateSignature::new(),
+        };
+
+        assert_eq!(
+            process_attestation(&mut state, &attestation, &mut CommitteeCache::new()),
+            Err(TransitionError::CommitteeIndexOutOfRange { index: 2, count: 2 })
+        );
+        assert!(state.current_epoch_attestations.is_empty());
+    }
 }
@@ -1,4 +1,5 @@
 use crate::attestations::attestations::AttestableBlock;
+use crate::error::TransitionError;
 use crate::rewards_and_penalties::rewards_and_penalties::StakeholderBlock;
 use helper_functions::beacon_state_accessors::*;
 use helper_functions::{
@@ -24,12 +25,13 @@ use types::{
     types::{Checkpoint, Eth1Data, HistoricalBatch, Validator},
 };
 
-pub fn process_epoch<T: Config>(state: &mut BeaconState<T>) {
+pub fn process_epoch<T: Config>(state: &mut BeaconState<T>) -> Result<(), TransitionError> {
     process_justification_and_finalization(state);
-    process_rewards_and_penalties(state);
-    process_registry_updates(state);
-    process_slashings(state);
+    process_rewards_and_penalties(state)?;
+    process_registry_updates(state)?;
+    process_slashings(state)?;
     process_final_updates(state);
+    Ok(())
 }
 
 fn process_justification_and_finalization<T: Config>(
@@ -99,7 +101,9 @@ fn process_justification_and_finalization<T: Config>(
     Ok(())
 }
 
-fn process_registry_updates<T: Config>(state: &mut BeaconState<T>) {
+fn process_registry_updates<T: Config>(
+    state: &mut BeaconState<T>,
+) -> Result<(), TransitionError> {
     let state_copy = state.clone();
 
     let is_eligible = |validator: &Validator| {
@@ -129,7 +133,7 @@ fn process_registry_updates<T: Config>(state: &mut BeaconState<T>) {
         state.validators[index].activation_eligibility_epoch = get_current_epoch(&state_copy);
     }
     for index in exiting {
-        initiate_validator_exit(state, index as u64).unwrap();
+        initiate_validator_exit(state, index as u64)?;
     }
 
     // Queue validators eligible for activation and not dequeued for activation prior to finalized epoch
@@ -147,7 +151,7 @@ fn process_registry_updates<T: Config>(state: &mut BeaconState<T>) {
         .collect_vec();
     // Dequeued validators for activation up to churn limit (without resetting activation epoch)
 
-    let churn_limit = get_validator_churn_limit(&state).unwrap();
+    let churn_limit = get_validator_churn_limit(&state)?;
     let delayed_activation_epoch =
         compute_activation_exit_epoch::<T>(get_current_epoch(state) as u64);
     for index in activation_queue.into_iter().take(churn_limit as usize) {
@@ -156,36 +160,44 @@ fn process_registry_updates<T: Config>(state: &mut BeaconState<T>) {
             validator.activation_epoch = delayed_activation_epoch;
         }
     }
+    Ok(())
 }
 
-fn process_rewards_and_penalties<T: Config>(state: &mut BeaconState<T>) -> Result<(), Error> {
+fn process_rewards_and_penalties<T: Config>(
+    state: &mut BeaconState<T>,
+) -> Result<(), TransitionError> {
     if get_current_epoch(state) == T::genesis_epoch() {
         return Ok(());
     }
     let (rewards, penalties) = state.get_attestation_deltas();
     for (index, validator) in state.validators.clone().iter_mut().enumerate() {
-        increase_balance(state, index as u64, rewards[index]).unwrap();
-        decrease_balance(state, index as u64, penalties[index]).unwrap();
+        increase_balance(state, index as u64, rewards[index])?;
+        decrease_balance(state, index as u64, penalties[index])?;
     }
     Ok(())
 }
 
-fn process_slashings<T: Config>(state: &mut BeaconState<T>) {
+fn process_slashings<T: Config>(state: &mut BeaconState<T>) -> Result<(), TransitionError> {
     let epoch = get_current_epoch(state);
-    let total_balance = get_total_active_balance(state).unwrap();
+    let total_balance = get_total_active_balance(state)?;
 
     for (index, validator) in state.validators.clone().iter_mut().enumerate() {
         if validator.slashed
             && epoch + T::EpochsPerSlashingsVector::U64 / 2 == validator.withdrawable_epoch
         {
             let increment = T::effective_balance_increment();
-            let slashings_sum = state.slashings.iter().sum::<u64>();
-            let penalty_numerator = validator.effective_balance / increment
-                * cmp::min(slashings_sum * 3, total_balance);
-            let penalty = penalty_numerator / total_balance * increment;
-            decrease_balance(state, index as u64, penalty).unwrap();
+            // The intermediate products can exceed `u64::max_value()` with mainnet-scale balances.
+            let slashings_sum = state.slashings.iter().map(|&s| u128::from(s)).sum::<u128>();
+            let penalty_numerator = u128::from(validator.effective_balance / increment)
+                * cmp::min(
+                    slashings_sum * u128::from(T::proportional_slashing_multiplier()),
+                    u128::from(total_balance),
+                );
+            let penalty = (penalty_numerator / u128::from(total_balance)) as u64 * increment;
+            decrease_balance(state, index as u64, penalty)?;
         }
     }
+    Ok(())
 }
 
 fn process_final_updates<T: Config>(state: &mut BeaconState<T>) {
@@ -196,11 +208,13 @@ fn process_final_updates<T: Config>(state: &mut BeaconState<T>) {
         state.eth1_data_votes = VariableList::from(vec![]);
     }
     //# Update effective balances with hysteresis
+    let hysteresis_increment = T::effective_balance_increment() / T::hysteresis_quotient();
+    let downward_threshold = hysteresis_increment * T::hysteresis_downward_multiplier();
+    let upward_threshold = hysteresis_increment * T::hysteresis_upward_multiplier();
     for (index, validator) in state.validators.iter_mut().enumerate() {
         let balance = state.balances[index];
-        let half_increment = T::effective_balance_increment() / 2;
-        if balance < validator.effective_balance
-            || validator.effective_balance + 3 * half_increment < balance
+        if balance + downward_threshold < validator.effective_balance
+            || validator.effective_balance + upward_threshold < balance
         {
             validator.effective_balance = cmp::min(
                 balance - balance % T::effective_balance_increment(),
@@ -233,7 +247,8 @@ fn process_final_updates<T: Config>(state: &mut BeaconState<T>) {
 mod process_epoch_tests {
     use super::*;
     // use mockall::mock;
-    use types::config::MainnetConfig;
+    use types::config::{MainnetConfig, MinimalConfig};
+    use types::helper_functions_types::Error as HelperError;
     /*
     mock! {
         BeaconState<C: Config + 'static> {}
@@ -245,6 +260,261 @@ mod process_epoch_tests {
     }
     */
 
+    fn effective_balance_after_final_updates(effective_balance: Gwei, balance: Gwei) -> Gwei {
+        let mut bs: BeaconState<MinimalConfig> = BeaconState::default();
+        bs.validators
+            .push(Validator {
+                effective_balance,
+                ..Validator::default()
+            })
+            .unwrap();
+        bs.balances.push(balance).unwrap();
+
+        process_final_updates(&mut bs);
+
+        bs.validators[0].effective_balance
+    }
+
+    #[test]
+    fn test_effective_balance_hysteresis() {
+        let increment = MinimalConfig::effective_balance_increment();
+        let hysteresis_increment = increment / MinimalConfig::hysteresis_quotient();
+        let downward_threshold =
+            hysteresis_increment * MinimalConfig::hysteresis_downward_multiplier();
+        let upward_threshold = hysteresis_increment * MinimalConfig::hysteresis_upward_multiplier();
+
+        let effective_balance = 30 * increment;
+
+        // At the downward threshold the effective balance is kept.
+        assert_eq!(
+            effective_balance_after_final_updates(
+                effective_balance,
+                effective_balance - downward_threshold,
+            ),
+            effective_balance
+        );
+        // Just below it the effective balance is rounded down from the balance.
+        assert_eq!(
+            effective_balance_after_final_updates(
+                effective_balance,
+                effective_balance - downward_threshold - 1,
+            ),
+            effective_balance - increment
+        );
+        // At the upward threshold the effective balance is kept.
+        assert_eq!(
+            effective_balance_after_final_updates(
+                effective_balance,
+                effective_balance + upward_threshold,
+            ),
+            effective_balance
+        );
+        // Just above it the effective balance is rounded down from the balance.
+        assert_eq!(
+            effective_balance_after_final_updates(
+                effective_balance,
+                effective_balance + upward_threshold + 1,
+            ),
+            effective_balance + increment
+        );
+    }
+
+    fn balance_after_process_slashings(epoch: Epoch, withdrawable_epoch: Epoch) -> Gwei {
+        let mut bs: BeaconState<MinimalConfig> = BeaconState {
+            slot: epoch * <MinimalConfig as Config>::SlotsPerEpoch::U64,
+            ..BeaconState::default()
+        };
+        let slashed = Validator {
+            effective_balance: MinimalConfig::max_effective_balance(),
+            slashed: true,
+            activation_epoch: 0,
+            exit_epoch: FAR_FUTURE_EPOCH,
+            withdrawable_epoch,
+            ..Validator::default()
+        };
+        let honest = Validator {
+            slashed: false,
+            withdrawable_epoch: FAR_FUTURE_EPOCH,
+            ..slashed.clone()
+        };
+        bs.validators.push(slashed).unwrap();
+        bs.validators.push(honest).unwrap();
+        bs.balances
+            .push(MinimalConfig::max_effective_balance())
+            .unwrap();
+        bs.balances
+            .push(MinimalConfig::max_effective_balance())
+            .unwrap();
+        bs.slashings[0] = MinimalConfig::max_effective_balance();
+
+        process_slashings(&mut bs).expect("slashings should be processed");
+
+        bs.balances[0]
+    }
+
+    #[test]
+    fn test_process_slashings_penalty_timing() {
+        let epoch = 10;
+        let midpoint = epoch + <MinimalConfig as Config>::EpochsPerSlashingsVector::U64 / 2;
+        let initial_balance = MinimalConfig::max_effective_balance();
+
+        assert!(balance_after_process_slashings(epoch, midpoint) < initial_balance);
+        assert_eq!(
+            balance_after_process_slashings(epoch, midpoint - 1),
+            initial_balance
+        );
+        assert_eq!(
+            balance_after_process_slashings(epoch, midpoint + 1),
+            initial_balance
+        );
+    }
+
+    #[test]
+    fn test_process_slashings_large_balances() {
+        let epoch = 10;
+        let slashed_balance = MinimalConfig::max_effective_balance();
+        let mut bs: BeaconState<MinimalConfig> = BeaconState {
+            slot: epoch * <MinimalConfig as Config>::SlotsPerEpoch::U64,
+            ..BeaconState::default()
+        };
+        let slashed = Validator {
+            effective_balance: slashed_balance,
+            slashed: true,
+            activation_epoch: 0,
+            exit_epoch: FAR_FUTURE_EPOCH,
+            withdrawable_epoch: epoch
+                + <MinimalConfig as Config>::EpochsPerSlashingsVector::U64 / 2,
+            ..Validator::default()
+        };
+        let honest = Validator {
+            effective_balance: 10_000_000_000_000_000_000,
+            slashed: false,
+            withdrawable_epoch: FAR_FUTURE_EPOCH,
+            ..slashed.clone()
+        };
+        bs.validators.push(slashed).unwrap();
+        bs.validators.push(honest).unwrap();
+        bs.balances.push(slashed_balance).unwrap();
+        bs.balances.push(slashed_balance).unwrap();
+        // Both `slashings_sum * 3` and the penalty numerator overflow `u64` here.
+        bs.slashings[0] = 10_000_000_000_000_000_000;
+
+        process_slashings(&mut bs).expect("slashings should be processed");
+
+        assert_eq!(bs.balances[0], 0);
+    }
+
+    #[test]
+    fn test_process_slashings_missing_balance() {
+        let epoch = 10;
+        let mut bs: BeaconState<MinimalConfig> = BeaconState {
+            slot: epoch * <MinimalConfig as Config>::SlotsPerEpoch::U64,
+            ..BeaconState::default()
+        };
+        let slashed = Validator {
+            effective_balance: MinimalConfig::max_effective_balance(),
+            slashed: true,
+            exit_epoch: FAR_FUTURE_EPOCH,
+            withdrawable_epoch: epoch
+                + <MinimalConfig as Config>::EpochsPerSlashingsVector::U64 / 2,
+            ..Validator::default()
+        };
+        bs.validators.push(slashed).unwrap();
+        // The validator has no corresponding entry in `balances`.
+        bs.slashings[0] = MinimalConfig::max_effective_balance();
+
+        assert_eq!(
+            process_slashings(&mut bs),
+            Err(TransitionError::Helper(HelperError::IndexOutOfRange))
+        );
+    }
+
+    // `MinimalConfig` with a proportional slashing multiplier of 1 instead of 3.
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Debug)]
+    struct SingleMultiplierConfig;
+
+    impl Config for SingleMultiplierConfig {
+        type EpochsPerSlashingsVector = <MinimalConfig as Config>::EpochsPerSlashingsVector;
+        type EpochsPerHistoricalVector = <MinimalConfig as Config>::EpochsPerHistoricalVector;
+        type HistoricalRootsLimit = <MinimalConfig as Config>::HistoricalRootsLimit;
+        type MaxAttesterSlashings = <MinimalConfig as Config>::MaxAttesterSlashings;
+        type MaxAttestations = <MinimalConfig as Config>::MaxAttestations;
+        type MaxAttestationsPerEpoch = <MinimalConfig as Config>::MaxAttestationsPerEpoch;
+        type MaxDeposits = <MinimalConfig as Config>::MaxDeposits;
+        type MaxProposerSlashings = <MinimalConfig as Config>::MaxProposerSlashings;
+        type MaxValidatorsPerCommittee = <MinimalConfig as Config>::MaxValidatorsPerCommittee;
+        type MaxVoluntaryExits = <MinimalConfig as Config>::MaxVoluntaryExits;
+        type SecondsPerSlot = <MinimalConfig as Config>::SecondsPerSlot;
+        type SlotsPerEpoch = <MinimalConfig as Config>::SlotsPerEpoch;
+        type SlotsPerEth1VotingPeriod = <MinimalConfig as Config>::SlotsPerEth1VotingPeriod;
+        type SlotsPerHistoricalRoot = <MinimalConfig as Config>::SlotsPerHistoricalRoot;
+        type ValidatorRegistryLimit = <MinimalConfig as Config>::ValidatorRegistryLimit;
+
+        fn genesis_fork_version() -> Version {
+            MinimalConfig::genesis_fork_version()
+        }
+        fn max_committees_per_slot() -> u64 {
+            MinimalConfig::max_committees_per_slot()
+        }
+        fn shuffle_round_count() -> u64 {
+            MinimalConfig::shuffle_round_count()
+        }
+        fn target_committee_size() -> u64 {
+            MinimalConfig::target_committee_size()
+        }
+
+        fn proportional_slashing_multiplier() -> u64 {
+            1
+        }
+    }
+
+    fn slashing_penalty<C: Config>(slashings_sum: Gwei) -> Gwei {
+        let epoch = 10;
+        let mut bs: BeaconState<C> = BeaconState {
+            slot: epoch * C::SlotsPerEpoch::U64,
+            ..BeaconState::default()
+        };
+        let slashed = Validator {
+            effective_balance: C::max_effective_balance(),
+            slashed: true,
+            activation_epoch: 0,
+            exit_epoch: FAR_FUTURE_EPOCH,
+            withdrawable_epoch: epoch + C::EpochsPerSlashingsVector::U64 / 2,
+            ..Validator::default()
+        };
+        let honest = Validator {
+            slashed: false,
+            withdrawable_epoch: FAR_FUTURE_EPOCH,
+            ..slashed.clone()
+        };
+        bs.validators.push(slashed).unwrap();
+        bs.validators.push(honest).unwrap();
+        bs.balances.push(C::max_effective_balance()).unwrap();
+        bs.balances.push(C::max_effective_balance()).unwrap();
+        bs.slashings[0] = slashings_sum;
+
+        process_slashings(&mut bs).expect("slashings should be processed");
+
+        C::max_effective_balance() - bs.balances[0]
+    }
+
+    #[test]
+    fn test_process_slashings_penalty_scales_with_multiplier() {
+        let increment = MinimalConfig::effective_balance_increment();
+        let slashings_sum = 8 * increment;
+
+        // 32 * min(8 * 3, 64) / 64 = 12 increments.
+        assert_eq!(
+            slashing_penalty::<MinimalConfig>(slashings_sum),
+            12 * increment
+        );
+        // 32 * min(8 * 1, 64) / 64 = 4 increments.
+        assert_eq!(
+            slashing_penalty::<SingleMultiplierConfig>(slashings_sum),
+            4 * increment
+        );
+    }
+
     // #[test]
     fn test_process_rewards_and_penalties() {
         let mut bs: BeaconState<MainnetConfig> = BeaconState {
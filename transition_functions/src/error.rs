@@ -0,0 +1,87 @@
+use thiserror::Error;
+use types::{
+    helper_functions_types::Error as HelperError,
+    primitives::{Epoch, Slot, ValidatorIndex, H256},
+};
+
+#[derive(PartialEq, Debug, Error)]
+pub enum TransitionError {
+    #[error("cannot process slots up to {slot} because state is already at slot {state_slot}")]
+    SlotInPast { state_slot: Slot, slot: Slot },
+    #[error("block slot does not match state slot")]
+    SlotMismatch,
+    #[error("block parent root does not match latest block header")]
+    ParentRootMismatch,
+    #[error("block proposer is slashed")]
+    ProposerSlashed,
+    #[error("block proposer signature is invalid")]
+    InvalidProposerSignature,
+    #[error("RANDAO reveal is invalid")]
+    InvalidRandaoReveal,
+    #[error("block state root {expected:?} does not match computed state root {found:?}")]
+    StateRootMismatch { expected: H256, found: H256 },
+    #[error("expected {expected} deposits in block, found {actual}")]
+    DepositCountMismatch { expected: usize, actual: usize },
+    #[error("proposer slashing headers are from different slots")]
+    ProposerSlashingSlotMismatch,
+    #[error("proposer slashing headers are identical")]
+    ProposerSlashingIdenticalHeaders,
+    #[error("proposer slashing signature is invalid")]
+    InvalidProposerSlashingSignature,
+    #[error("validator {index} is not slashable")]
+    ValidatorNotSlashable { index: ValidatorIndex },
+    #[error("attester slashing attestation data is not slashable")]
+    AttestationDataNotSlashable,
+    #[error("attester slashing did not slash any validators")]
+    NoValidatorsSlashed,
+    #[error("attestation committee index {index} is not less than committee count {count}")]
+    CommitteeIndexOutOfRange { index: u64, count: u64 },
+    #[error("attestation target epoch {epoch} is neither the previous nor the current epoch")]
+    TargetEpochOutOfRange { epoch: Epoch },
+    #[error("attestation is not within the inclusion window")]
+    AttestationOutsideInclusionWindow,
+    #[error("attestation has {bits} aggregation bits but committee has {committee_size} members")]
+    AggregationBitsLengthMismatch { bits: usize, committee_size: usize },
+    #[error("attestation source does not match justified checkpoint")]
+    SourceMismatch,
+    #[error("attestation signature is invalid")]
+    InvalidAttestationSignature,
+    #[error("deposit Merkle proof is invalid")]
+    InvalidDepositProof,
+    #[error("validator {index} is not active")]
+    ValidatorNotActive { index: ValidatorIndex },
+    #[error("validator {index} has already exited")]
+    ValidatorAlreadyExited { index: ValidatorIndex },
+    #[error("voluntary exit epoch {epoch} is in the future")]
+    VoluntaryExitTooEarly { epoch: Epoch },
+    #[error("validator {index} has not been active long enough to exit")]
+    ValidatorTooYoung { index: ValidatorIndex },
+    #[error("voluntary exit signature is invalid")]
+    InvalidVoluntaryExitSignature,
+    #[error("validator index {index} is out of range")]
+    ValidatorIndexOutOfRange { index: ValidatorIndex },
+    #[error("public key cannot be converted to bytes")]
+    InvalidPublicKey,
+    #[error("signature cannot be converted to bytes")]
+    InvalidSignatureBytes,
+    #[error("helper function failed: {0:?}")]
+    Helper(HelperError),
+}
+
+impl From<HelperError> for TransitionError {
+    fn from(error: HelperError) -> Self {
+        match error {
+            HelperError::BlockSlotMismatch => Self::SlotMismatch,
+            HelperError::BlockParentMismatch => Self::ParentRootMismatch,
+            HelperError::ProposerSlashed => Self::ProposerSlashed,
+            HelperError::InvalidSignature => Self::InvalidAttestationSignature,
+            HelperError::CommitteeLengthMismatch { expected, got } => {
+                Self::AggregationBitsLengthMismatch {
+                    bits: got,
+                    committee_size: expected,
+                }
+            }
+            error => Self::Helper(error),
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use crate::blocks::block_processing::process_deposit;
+use crate::error::TransitionError;
+use helper_functions::{
+    beacon_state_accessors::get_active_validator_indices, crypto::hash_tree_root,
+};
+use ssz_types::{FixedVector, VariableList};
+use std::convert::TryFrom;
+use typenum::U4294967296;
+use types::{
+    beacon_state::BeaconState,
+    config::Config,
+    consts::SECONDS_PER_DAY,
+    primitives::H256,
+    types::{BeaconBlockBody, BeaconBlockHeader, Deposit, DepositData, Eth1Data, Fork},
+};
+
+type DepositDataList = VariableList<DepositData, U4294967296>;
+
+pub fn initialize_beacon_state_from_eth1<C: Config>(
+    eth1_block_hash: H256,
+    eth1_timestamp: u64,
+    deposits: &[Deposit],
+) -> Result<BeaconState<C>, TransitionError> {
+    let mut state = BeaconState {
+        genesis_time: eth1_timestamp - eth1_timestamp % SECONDS_PER_DAY + 2 * SECONDS_PER_DAY,
+        fork: Fork {
+            previous_version: C::genesis_fork_version(),
+            current_version: C::genesis_fork_version(),
+            epoch: C::genesis_epoch(),
+        },
+        eth1_data: Eth1Data {
+            block_hash: eth1_block_hash,
+            deposit_count: deposits.len() as u64,
+            ..Eth1Data::default()
+        },
+        latest_block_header: BeaconBlockHeader {
+            body_root: hash_tree_root(&BeaconBlockBody::<C>::default()),
+            ..BeaconBlockHeader::default()
+        },
+        randao_mixes: FixedVector::from_elem(eth1_block_hash),
+        ..BeaconState::default()
+    };
+
+    //# Process deposits
+    let leaves = deposits
+        .iter()
+        .map(|deposit| deposit.data.clone())
+        .collect::<Vec<_>>();
+    for (index, deposit) in deposits.iter().enumerate() {
+        let deposit_data_list = DepositDataList::from(leaves[..=index].to_vec());
+        state.eth1_data.deposit_root = hash_tree_root(&deposit_data_list);
+        process_deposit(&mut state, deposit)?;
+    }
+
+    //# Process activations
+    for (index, validator) in state.validators.iter_mut().enumerate() {
+        let balance = state.balances[index];
+        validator.effective_balance = std::cmp::min(
+            balance - balance % C::effective_balance_increment(),
+            C::max_effective_balance(),
+        );
+        if validator.effective_balance == C::max_effective_balance() {
+            validator.activation_eligibility_epoch = C::genesis_epoch();
+            validator.activation_epoch = C::genesis_epoch();
+        }
+    }
+
+    Ok(state)
+}
+
+pub fn is_valid_genesis_state<C: Config>(state: &BeaconState<C>) -> bool {
+    if state.genesis_time < C::min_genesis_time() {
+        return false;
+    }
+    let active_validator_count = get_active_validator_indices(state, C::genesis_epoch()).len();
+    u64::try_from(active_validator_count).expect("validator count should fit in u64")
+        >= C::min_genesis_active_validator_count()
+}
+
+#[cfg(test)]
+mod genesis_tests {
+    use super::*;
+    use bls::{PublicKey, PublicKeyBytes, SecretKey, Signature, SignatureBytes};
+    use helper_functions::{
+        crypto::{hash, signed_root},
+        misc::compute_domain,
+    };
+    use types::{config::MinimalConfig, consts::DEPOSIT_CONTRACT_TREE_DEPTH};
+
+    fn hash_pair(left: H256, right: H256) -> H256 {
+        H256::from_slice(&hash(&[left.as_bytes(), right.as_bytes()].concat()))
+    }
+
+    fn zero_hash(height: u64) -> H256 {
+        (0..height).fold(H256::zero(), |node, _| hash_pair(node, node))
+    }
+
+    // The root of the subtree of height `height` at position `index` in a Merkle tree whose leaves
+    // are `leaves` followed by zero hashes.
+    fn merkle_node(leaves: &[H256], height: u64, index: usize) -> H256 {
+        if index << height >= leaves.len() {
+            return zero_hash(height);
+        }
+        if height == 0 {
+            return leaves[index];
+        }
+        hash_pair(
+            merkle_node(leaves, height - 1, 2 * index),
+            merkle_node(leaves, height - 1, 2 * index + 1),
+        )
+    }
+
+    // Proves that `leaves[index]` is in the deposit tree containing `leaves`.
+    fn deposit_proof(leaves: &[H256], index: usize) -> Vec<H256> {
+        let mut proof = (0..DEPOSIT_CONTRACT_TREE_DEPTH)
+            .map(|height| {
+                let sibling = (index >> height) ^ 1;
+                merkle_node(leaves, height, sibling)
+            })
+            .collect::<Vec<_>>();
+        let mut length = [0; 32];
+        length[..8].copy_from_slice(&(leaves.len() as u64).to_le_bytes());
+        proof.push(H256::from(length));
+        proof
+    }
+
+    fn deposit_data(count: usize) -> Vec<DepositData> {
+        let domain = compute_domain(MinimalConfig::domain_deposit(), None);
+        (0..count)
+            .map(|_| {
+                let secret_key = SecretKey::random();
+                let public_key = PublicKey::from_secret_key(&secret_key);
+                let mut data = DepositData {
+                    pubkey: PublicKeyBytes::from_bytes(&public_key.as_bytes())
+                        .expect("public key should be valid"),
+                    withdrawal_credentials: H256::zero(),
+                    amount: MinimalConfig::max_effective_balance(),
+                    signature: SignatureBytes::from_bytes(&[0; 96])
+                        .expect("signature should be valid"),
+                };
+                let signature = Signature::new(signed_root(&data).as_bytes(), domain, &secret_key);
+                data.signature = SignatureBytes::from_bytes(&signature.as_bytes())
+                    .expect("signature should be valid");
+                data
+            })
+            .collect()
+    }
+
+    // Each deposit is proven against the deposit tree at the time it was made, as in genesis.
+    fn deposits(count: usize) -> Vec<Deposit> {
+        let data = deposit_data(count);
+        let leaves = data.iter().map(hash_tree_root).collect::<Vec<_>>();
+        data.into_iter()
+            .enumerate()
+            .map(|(index, data)| Deposit {
+                proof: FixedVector::from(deposit_proof(&leaves[..=index], index)),
+                data,
+            })
+            .collect()
+    }
+
+    // Every deposit is proven against the same deposit tree, as in a block.
+    fn deposits_in_tree(count: usize) -> (Vec<Deposit>, H256) {
+        let data = deposit_data(count);
+        let deposit_root = hash_tree_root(&DepositDataList::from(data.clone()));
+        let leaves = data.iter().map(hash_tree_root).collect::<Vec<_>>();
+        let deposits = data
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| Deposit {
+                proof: FixedVector::from(deposit_proof(&leaves, index)),
+                data,
+            })
+            .collect();
+        (deposits, deposit_root)
+    }
+
+    #[test]
+    fn genesis_state_from_deposits() {
+        let count = MinimalConfig::min_genesis_active_validator_count() as usize;
+        let deposits = deposits(count);
+
+        let state = initialize_beacon_state_from_eth1::<MinimalConfig>(
+            H256::repeat_byte(1),
+            MinimalConfig::min_genesis_time(),
+            &deposits,
+        )
+        .expect("genesis deposits should be valid");
+
+        assert_eq!(state.validators.len(), count);
+        assert_eq!(state.balances.len(), count);
+        assert_eq!(state.eth1_deposit_index, count as u64);
+        assert!(state
+            .validators
+            .iter()
+            .all(|validator| validator.activation_epoch == MinimalConfig::genesis_epoch()));
+        assert!(is_valid_genesis_state(&state));
+    }
+
+    #[test]
+    fn too_few_validators_for_genesis() {
+        let deposits = deposits(4);
+
+        let state = initialize_beacon_state_from_eth1::<MinimalConfig>(
+            H256::repeat_byte(1),
+            MinimalConfig::min_genesis_time(),
+            &deposits,
+        )
+        .expect("genesis deposits should be valid");
+
+        assert_eq!(state.validators.len(), 4);
+        assert!(!is_valid_genesis_state(&state));
+    }
+
+    #[test]
+    fn genesis_fork_uses_configured_version() {
+        let state = initialize_beacon_state_from_eth1::<MinimalConfig>(
+            H256::repeat_byte(1),
+            MinimalConfig::min_genesis_time(),
+            &deposits(1),
+        )
+        .expect("genesis deposits should be valid");
+
+        assert_eq!(state.fork.previous_version, [0, 0, 0, 1]);
+        assert_eq!(state.fork.current_version, [0, 0, 0, 1]);
+        assert_eq!(state.fork.epoch, MinimalConfig::genesis_epoch());
+    }
+
+    #[test]
+    fn deposits_processed_in_order() {
+        let (deposits, deposit_root) = deposits_in_tree(2);
+        let state: BeaconState<MinimalConfig> = BeaconState {
+            eth1_data: Eth1Data {
+                deposit_root,
+                deposit_count: 2,
+                ..Eth1Data::default()
+            },
+            ..BeaconState::default()
+        };
+
+        let mut in_order = state.clone();
+        for deposit in &deposits {
+            process_deposit(&mut in_order, deposit).expect("deposit should be valid");
+        }
+        assert_eq!(in_order.eth1_deposit_index, 2);
+        assert_eq!(in_order.validators.len(), 2);
+        for (validator, deposit) in in_order.validators.iter().zip(&deposits) {
+            assert_eq!(validator.pubkey.as_bytes(), deposit.data.pubkey.as_bytes());
+        }
+
+        // The proof for the second deposit is checked against index 0.
+        let mut out_of_order = state.clone();
+        assert_eq!(
+            process_deposit(&mut out_of_order, &deposits[1]),
+            Err(TransitionError::InvalidDepositProof)
+        );
+        assert_eq!(out_of_order, state);
+
+        let mut bad_proof = deposits[0].clone();
+        bad_proof.proof[0] = H256::repeat_byte(0xff);
+        let mut with_bad_proof = state.clone();
+        assert_eq!(
+            process_deposit(&mut with_bad_proof, &bad_proof),
+            Err(TransitionError::InvalidDepositProof)
+        );
+        assert_eq!(with_bad_proof, state);
+    }
+}
@@ -6,5 +6,8 @@
 pub mod attestations;
 pub mod blocks;
 pub mod epochs;
+pub mod error;
+pub mod genesis;
 pub mod process_slot;
 pub mod rewards_and_penalties;
+pub mod state_comparator;
@@ -1,6 +1,7 @@
 use crate::*;
 use blocks::block_processing::*;
 use epochs::process_epoch::process_epoch;
+use error::TransitionError;
 use ethereum_types::H256 as Hash256;
 use helper_functions;
 use helper_functions::crypto::*;
@@ -11,38 +12,70 @@ use types::{
     beacon_state::BeaconState,
     config::Config,
     primitives::{Slot, H256},
-    types::BeaconBlock,
+    types::{BeaconBlock, SignedBeaconBlock},
 };
+
 #[derive(Debug, PartialEq)]
 pub enum Error {}
 
 pub fn state_transition<T: Config>(
     state: &mut BeaconState<T>,
-    block: &BeaconBlock<T>,
+    signed_block: &SignedBeaconBlock<T>,
     validate_state_root: bool,
-) -> BeaconState<T> {
+) -> Result<(), TransitionError> {
+    let block = &signed_block.message;
     //# Process slots (including those with no blocks) since block
-    process_slots(state, block.slot);
+    process_slots(state, block.slot)?;
+    //# Verify signature
+    verify_block_signature(state, signed_block)?;
     //# Process block
-    blocks::block_processing::process_block(state, block);
+    blocks::block_processing::process_block(state, block)?;
     //# Validate state root (`validate_state_root == True` in production)
     if validate_state_root {
-        assert!(block.state_root == hash_tree_root(state));
+        let state_root = hash_tree_root(state);
+        if block.state_root != state_root {
+            return Err(TransitionError::StateRootMismatch {
+                expected: block.state_root,
+                found: state_root,
+            });
+        }
     }
-    //# Return post-state
-    return state.clone();
+    Ok(())
+}
+
+/// Returns the root of the state produced by applying `block` to `parent_state`.
+///
+/// Meant for block producers filling in `block.state_root` before signing the block, so the
+/// state root in `block` is ignored and no proposer signature is needed.
+pub fn compute_state_root_for_block<T: Config>(
+    parent_state: &BeaconState<T>,
+    block: &BeaconBlock<T>,
+) -> Result<H256, TransitionError> {
+    let mut state = parent_state.clone();
+    process_slots(&mut state, block.slot)?;
+    process_block(&mut state, block)?;
+    Ok(hash_tree_root(&state))
 }
 
-pub fn process_slots<T: Config>(state: &mut BeaconState<T>, slot: Slot) {
-    // assert!(state.slot <= slot);
+pub fn process_slots<T: Config>(
+    state: &mut BeaconState<T>,
+    slot: Slot,
+) -> Result<(), TransitionError> {
+    if slot < state.slot {
+        return Err(TransitionError::SlotInPast {
+            state_slot: state.slot,
+            slot,
+        });
+    }
     while state.slot < slot {
         process_slot(state);
         //# Process epoch on the start slot of the next epoch
         if (state.slot + 1) % T::SlotsPerEpoch::U64 == 0 {
-            process_epoch(state);
+            process_epoch(state)?;
         }
         state.slot += 1;
     }
+    Ok(())
 }
 
 fn process_slot<T: Config>(state: &mut BeaconState<T>) {
@@ -57,7 +90,7 @@ fn process_slot<T: Config>(state: &mut BeaconState<T>) {
     }
     // Cache block root
     // Old doc
-    let previous_block_root = signed_root(&state.latest_block_header);
+    let previous_block_root = hash_tree_root(&state.latest_block_header);
     state.block_roots[(state.slot as usize) % T::SlotsPerHistoricalRoot::USIZE] =
         previous_block_root;
 }
@@ -78,10 +111,14 @@ fn process_slot<T: Config>(state: &mut BeaconState<T>) {
 
 #[cfg(test)]
 mod process_slot_tests {
-    use helper_functions::beacon_state_accessors::get_current_epoch;
-    use ssz_types::FixedVector;
+    use bls::{PublicKey, SecretKey, Signature};
+    use helper_functions::beacon_state_accessors::{get_current_epoch, get_domain};
+    use ssz_types::{FixedVector, VariableList};
     use std::iter;
-    use types::{beacon_state::*, config::MainnetConfig};
+    use types::{
+        beacon_state::*,
+        config::{MainnetConfig, MinimalConfig},
+    };
 
     // use crate::{config::*};
     use super::*;
@@ -95,7 +132,7 @@ mod process_slot_tests {
             ..BeaconState::default()
         };
 
-        process_slots(&mut bs, 1);
+        process_slots(&mut bs, 1).expect("slots should be processed");
 
         assert_eq!(bs.slot, 1);
     }
@@ -108,7 +145,7 @@ mod process_slot_tests {
             slot: 3,
             ..BeaconState::default()
         };
-        process_slots(&mut bs, 4);
+        process_slots(&mut bs, 4).expect("slots should be processed");
         assert_eq!(bs.slot, 4);
     }
 
@@ -125,10 +162,167 @@ mod process_slot_tests {
             slot: 0,
             ..BeaconState::default()
         };
-        process_slots(&mut bs, 32);
+        process_slots(&mut bs, 32).expect("slots should be processed");
         assert_eq!(get_current_epoch(&bs), 1);
     }
 
+    #[test]
+    fn process_slots_fills_historical_roots() {
+        let mut temp: Vec<H256> = iter::repeat(H256::from_low_u64_be(0)).take(8192).collect();
+        let mut bs: BeaconState<MainnetConfig> = BeaconState {
+            block_roots: FixedVector::new(temp.clone()).unwrap(),
+            state_roots: FixedVector::new(temp.clone()).unwrap(),
+            ..BeaconState::default()
+        };
+
+        process_slots(&mut bs, 5).expect("slots should be processed");
+
+        assert_eq!(bs.slot, 5);
+        assert_eq!(bs.latest_block_header.state_root, bs.state_roots[0]);
+        for slot in 0..5 {
+            assert_ne!(bs.state_roots[slot], H256::zero());
+            assert_eq!(
+                bs.block_roots[slot],
+                hash_tree_root(&bs.latest_block_header)
+            );
+        }
+        for slot in 1..5 {
+            assert_ne!(bs.state_roots[slot], bs.state_roots[slot - 1]);
+        }
+        assert_eq!(bs.state_roots[5], H256::zero());
+        assert_eq!(bs.block_roots[5], H256::zero());
+    }
+
+    #[test]
+    fn process_slots_rejects_past_slot() {
+        let mut temp: Vec<H256> = iter::repeat(H256::from_low_u64_be(0)).take(8192).collect();
+        let mut bs: BeaconState<MainnetConfig> = BeaconState {
+            block_roots: FixedVector::new(temp.clone()).unwrap(),
+            state_roots: FixedVector::new(temp.clone()).unwrap(),
+            slot: 3,
+            ..BeaconState::default()
+        };
+
+        assert_eq!(
+            process_slots(&mut bs, 2),
+            Err(TransitionError::SlotInPast {
+                state_slot: 3,
+                slot: 2,
+            })
+        );
+        assert_eq!(bs.slot, 3);
+    }
+
+    // Builds a state with a single active validator and a valid block for slot 1 on top of it.
+    fn state_and_next_block() -> (BeaconState<MinimalConfig>, SignedBeaconBlock<MinimalConfig>) {
+        let secret_key = SecretKey::random();
+        let state: BeaconState<MinimalConfig> = BeaconState {
+            validators: VariableList::from(vec![Validator {
+                pubkey: PublicKey::from_secret_key(&secret_key),
+                effective_balance: MinimalConfig::max_effective_balance(),
+                exit_epoch: u64::max_value(),
+                withdrawable_epoch: u64::max_value(),
+                ..Validator::default()
+            }]),
+            balances: VariableList::from(vec![MinimalConfig::max_effective_balance()]),
+            ..BeaconState::default()
+        };
+
+        let mut advanced_state = state.clone();
+        process_slots(&mut advanced_state, 1).expect("slots should be processed");
+        let randao_domain =
+            get_domain(&advanced_state, MinimalConfig::domain_randao() as u32, None);
+        let mut block = BeaconBlock {
+            slot: 1,
+            parent_root: hash_tree_root(&advanced_state.latest_block_header),
+            body: BeaconBlockBody {
+                randao_reveal: Signature::new(
+                    hash_tree_root(&get_current_epoch(&advanced_state)).as_bytes(),
+                    randao_domain,
+                    &secret_key,
+                ),
+                ..BeaconBlockBody::default()
+            },
+            ..BeaconBlock::default()
+        };
+        block.state_root =
+            compute_state_root_for_block(&state, &block).expect("block should be valid");
+
+        let proposer_domain = get_domain(
+            &advanced_state,
+            MinimalConfig::domain_beacon_proposer() as u32,
+            None,
+        );
+        let signed_block = SignedBeaconBlock {
+            signature: Signature::new(
+                hash_tree_root(&block).as_bytes(),
+                proposer_domain,
+                &secret_key,
+            ),
+            message: block,
+        };
+
+        (state, signed_block)
+    }
+
+    #[test]
+    fn state_transition_accepts_correct_state_root() {
+        let (mut state, signed_block) = state_and_next_block();
+
+        state_transition(&mut state, &signed_block, true).expect("state transition should succeed");
+
+        assert_eq!(hash_tree_root(&state), signed_block.message.state_root);
+    }
+
+    #[test]
+    fn compute_state_root_for_block_matches_state_transition() {
+        let (state, signed_block) = state_and_next_block();
+        let block = signed_block.message;
+        let mut post_state = state.clone();
+
+        process_slots(&mut post_state, block.slot).expect("slots should be processed");
+        process_block(&mut post_state, &block).expect("block should be valid");
+
+        assert_eq!(block.state_root, hash_tree_root(&post_state));
+    }
+
+    #[test]
+    fn state_transition_rejects_tampered_state_root() {
+        let (mut state, mut signed_block) = state_and_next_block();
+        let state_root = signed_block.message.state_root;
+        signed_block.message.state_root = H256::repeat_byte(1);
+
+        assert_eq!(
+            state_transition(&mut state, &signed_block, true),
+            Err(TransitionError::StateRootMismatch {
+                expected: H256::repeat_byte(1),
+                found: state_root,
+            })
+        );
+    }
+
+    #[test]
+    fn state_transition_rejects_block_signed_by_another_key() {
+        let (mut state, mut signed_block) = state_and_next_block();
+        let mut advanced_state = state.clone();
+        process_slots(&mut advanced_state, signed_block.message.slot)
+            .expect("slots should be processed");
+        signed_block.signature = Signature::new(
+            hash_tree_root(&signed_block.message).as_bytes(),
+            get_domain(
+                &advanced_state,
+                MinimalConfig::domain_beacon_proposer() as u32,
+                None,
+            ),
+            &SecretKey::random(),
+        );
+
+        assert_eq!(
+            state_transition(&mut state, &signed_block, true),
+            Err(TransitionError::InvalidProposerSignature)
+        );
+    }
+
     // #[test]
     // fn transition_state() {
     //     let mut vec_1: Vec<H256> = iter::repeat(H256::from_low_u64_be(0)).take(8192).collect();
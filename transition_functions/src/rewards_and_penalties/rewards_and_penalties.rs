@@ -6,6 +6,7 @@ use types::{
 };
 // use types::types::*;
 use crate::attestations::attestations::AttestableBlock;
+use crate::error::TransitionError;
 use helper_functions::beacon_state_accessors::*;
 use helper_functions::beacon_state_mutators::*;
 use helper_functions::math::*;
@@ -18,7 +19,7 @@ where
 {
     fn get_base_reward(&self, index: ValidatorIndex) -> Gwei;
     fn get_attestation_deltas(&self) -> (Vec<Gwei>, Vec<Gwei>);
-    fn process_rewards_and_penalties(&mut self);
+    fn process_rewards_and_penalties(&mut self) -> Result<(), TransitionError>;
 }
 
 impl<T> StakeholderBlock<T> for BeaconState<T>
@@ -111,27 +112,49 @@ where
         return (rewards, penalties);
     }
 
-    fn process_rewards_and_penalties(&mut self) {
+    fn process_rewards_and_penalties(&mut self) -> Result<(), TransitionError> {
         if get_current_epoch(&self) == T::genesis_epoch() {
-            return;
+            return Ok(());
         }
         let (rewards, penalties) = self.get_attestation_deltas();
         for (index, validator) in self.validators.clone().iter_mut().enumerate() {
-            increase_balance(self, index as u64, rewards[index]).unwrap();
-            decrease_balance(self, index as u64, penalties[index]).unwrap();
+            increase_balance(self, index as u64, rewards[index])?;
+            decrease_balance(self, index as u64, penalties[index])?;
         }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod process_slot_tests {
     use crate::rewards_and_penalties::rewards_and_penalties::StakeholderBlock;
+    use helper_functions::math::integer_squareroot;
     use types::{
         beacon_state::*,
         config::{Config, MainnetConfig},
+        consts::BASE_REWARDS_PER_EPOCH,
         types::Validator,
     };
 
+    #[test]
+    fn get_base_reward_without_active_validators() {
+        let mut bs: BeaconState<MainnetConfig> = BeaconState::default();
+        bs.validators
+            .push(Validator {
+                effective_balance: MainnetConfig::max_effective_balance(),
+                exit_epoch: 0,
+                ..Validator::default()
+            })
+            .unwrap();
+
+        assert_eq!(
+            bs.get_base_reward(0),
+            MainnetConfig::max_effective_balance() * MainnetConfig::base_reward_factor()
+                / integer_squareroot(MainnetConfig::effective_balance_increment())
+                / BASE_REWARDS_PER_EPOCH
+        );
+    }
+
     fn test() {
         // let mut bs: BeaconState<MainnetConfig> = BeaconState {
         //     ..BeaconState::default()
@@ -0,0 +1,150 @@
+use core::{cmp, fmt};
+use types::{beacon_state::BeaconState, config::Config};
+
+/// A field that differs between two `BeaconState`s.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FieldDiff {
+    pub field: &'static str,
+    /// The first index at which the values differ if the field is a list or vector.
+    pub index: Option<usize>,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.index {
+            Some(index) => write!(formatter, "{}[{}]", self.field, index),
+            None => write!(formatter, "{}", self.field),
+        }
+    }
+}
+
+/// Returns the fields that differ between `a` and `b` in declaration order.
+pub fn diff_states<C: Config>(a: &BeaconState<C>, b: &BeaconState<C>) -> Vec<FieldDiff> {
+    let mut diffs = vec![];
+
+    macro_rules! compare {
+        ($($field:ident),*) => {
+            $(
+                if a.$field != b.$field {
+                    diffs.push(FieldDiff {
+                        field: stringify!($field),
+                        index: None,
+                    });
+                }
+            )*
+        };
+    }
+
+    macro_rules! compare_elements {
+        ($($field:ident),*) => {
+            $(
+                if let Some(index) = first_difference(&a.$field, &b.$field) {
+                    diffs.push(FieldDiff {
+                        field: stringify!($field),
+                        index: Some(index),
+                    });
+                }
+            )*
+        };
+    }
+
+    compare!(genesis_time, slot, fork, latest_block_header);
+    compare_elements!(block_roots, state_roots, historical_roots);
+    compare!(eth1_data);
+    compare_elements!(eth1_data_votes);
+    compare!(eth1_deposit_index);
+    compare_elements!(
+        validators,
+        balances,
+        randao_mixes,
+        slashings,
+        previous_epoch_attestations,
+        current_epoch_attestations
+    );
+    compare!(
+        justification_bits,
+        previous_justified_checkpoint,
+        current_justified_checkpoint,
+        finalized_checkpoint
+    );
+
+    diffs
+}
+
+// If one slice is a prefix of the other, the first difference is at the end of the shorter one.
+fn first_difference<T: PartialEq>(a: &[T], b: &[T]) -> Option<usize> {
+    if a == b {
+        return None;
+    }
+    let index = a
+        .iter()
+        .zip(b)
+        .position(|(a, b)| a != b)
+        .unwrap_or_else(|| cmp::min(a.len(), b.len()));
+    Some(index)
+}
+
+#[cfg(test)]
+mod state_comparator_tests {
+    use super::*;
+    use types::config::MinimalConfig;
+
+    fn state() -> BeaconState<MinimalConfig> {
+        let mut state = BeaconState::default();
+        for balance in &[1, 2, 3] {
+            state.balances.push(*balance).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn equal_states_have_no_differences() {
+        assert_eq!(diff_states(&state(), &state()), []);
+    }
+
+    #[test]
+    fn reports_changed_field() {
+        let mut changed = state();
+        changed.slot = 1;
+
+        assert_eq!(
+            diff_states(&state(), &changed),
+            [FieldDiff {
+                field: "slot",
+                index: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_first_changed_element() {
+        let mut changed = state();
+        changed.balances[1] = 0;
+        changed.balances[2] = 0;
+
+        let diffs = diff_states(&state(), &changed);
+
+        assert_eq!(
+            diffs,
+            [FieldDiff {
+                field: "balances",
+                index: Some(1),
+            }]
+        );
+        assert_eq!(diffs[0].to_string(), "balances[1]");
+    }
+
+    #[test]
+    fn reports_end_of_shorter_list() {
+        let mut changed = state();
+        changed.balances.push(4).unwrap();
+
+        assert_eq!(
+            diff_states(&state(), &changed),
+            [FieldDiff {
+                field: "balances",
+                index: Some(3),
+            }]
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     config::*, consts, helper_functions_types::Error as HelperError, primitives::*, types::*,
 };
+use core::convert::TryFrom;
 use ethereum_types::H256 as Hash256;
 use serde::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
@@ -132,4 +133,61 @@ impl<C: Config> BeaconState<C> {
         self.block_roots[i] = block_root;
         Ok(())
     }
+
+    /// Returns `None` if there is no validator at `index`.
+    pub fn validator(&self, index: ValidatorIndex) -> Option<&Validator> {
+        self.validators.get(usize::try_from(index).ok()?)
+    }
+
+    /// Returns `None` if there is no validator at `index`.
+    pub fn balance(&self, index: ValidatorIndex) -> Option<Gwei> {
+        self.balances.get(usize::try_from(index).ok()?).copied()
+    }
+
+    /// Returns `None` if there is no validator at `index`.
+    pub fn effective_balance(&self, index: ValidatorIndex) -> Option<Gwei> {
+        self.validator(index)
+            .map(|validator| validator.effective_balance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> BeaconState<MinimalConfig> {
+        let mut state = BeaconState::default();
+        for balance in &[1, 2] {
+            state
+                .validators
+                .push(Validator {
+                    effective_balance: balance * 10,
+                    ..Validator::default()
+                })
+                .expect("validator registry should have room");
+            state
+                .balances
+                .push(*balance)
+                .expect("balance list should have room");
+        }
+        state
+    }
+
+    #[test]
+    fn accessors_in_range() {
+        let state = state();
+        assert_eq!(state.validator(1), Some(&state.validators[1]));
+        assert_eq!(state.balance(1), Some(2));
+        assert_eq!(state.effective_balance(1), Some(20));
+    }
+
+    #[test]
+    fn accessors_out_of_range() {
+        let state = state();
+        for index in &[2, u64::max_value()] {
+            assert_eq!(state.validator(*index), None);
+            assert_eq!(state.balance(*index), None);
+            assert_eq!(state.effective_balance(*index), None);
+        }
+    }
 }
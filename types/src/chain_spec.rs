@@ -64,7 +64,6 @@ pub struct ChainSpec {
     pub activation_exit_delay: u64,
     pub min_validator_withdrawability_delay: Epoch,
     pub persistent_committee_period: u64,
-    pub max_epochs_per_crosslink: u64,
     pub min_epochs_to_inactivity_penalty: u64,
 
     /*
@@ -164,7 +163,6 @@ impl ChainSpec {
             activation_exit_delay: 4,
             min_validator_withdrawability_delay: Epoch::new(256),
             persistent_committee_period: 2_048,
-            max_epochs_per_crosslink: 64,
             min_epochs_to_inactivity_penalty: 4,
 
             /*
@@ -207,7 +205,6 @@ impl ChainSpec {
             target_committee_size: 4,
             shuffle_round_count: 10,
             min_genesis_active_validator_count: 64,
-            max_epochs_per_crosslink: 4,
             network_id: 2, // lighthouse testnet network id
             boot_nodes,
             ..ChainSpec::mainnet()
@@ -6,7 +6,7 @@ use core::hash::Hash;
 use serde::{Deserialize, Serialize};
 use typenum::{NonZero, Prod, Unsigned};
 
-use crate::primitives::DomainType;
+use crate::primitives::{DomainType, Version};
 
 pub trait Config
 where
@@ -202,9 +202,21 @@ where
     fn genesis_epoch() -> u64 {
         0
     }
+    fn genesis_fork_version() -> Version {
+        [0, 0, 0, 0]
+    }
     fn genesis_slot() -> u64 {
         0
     }
+    fn hysteresis_downward_multiplier() -> u64 {
+        1
+    }
+    fn hysteresis_quotient() -> u64 {
+        4
+    }
+    fn hysteresis_upward_multiplier() -> u64 {
+        5
+    }
     fn inactivity_penalty_quotient() -> u64 {
         2_u64.pow(25)
     }
@@ -214,9 +226,6 @@ where
     fn max_effective_balance() -> u64 {
         32_000_000_000
     }
-    fn max_epochs_per_crosslink() -> u64 {
-        4
-    }
     fn min_attestation_inclusion_delay() -> u64 {
         1
     }
@@ -249,11 +258,14 @@ where
     fn persistent_committee_period() -> u64 {
         2_u64.pow(11)
     }
+    fn proportional_slashing_multiplier() -> u64 {
+        3
+    }
     fn proposer_reward_quotient() -> u64 {
         8
     }
     fn shuffle_round_count() -> u64 {
-        10
+        90
     }
     fn target_committee_size() -> u64 {
         128
@@ -308,10 +320,62 @@ impl Config for MinimalConfig {
     type SlotsPerHistoricalRoot = typenum::U64;
     type ValidatorRegistryLimit = typenum::U1099511627776;
 
+    fn genesis_fork_version() -> Version {
+        [0, 0, 0, 1]
+    }
     fn max_committees_per_slot() -> u64 {
         4
     }
+    fn shuffle_round_count() -> u64 {
+        10
+    }
     fn target_committee_size() -> u64 {
         4
     }
 }
+
+/// An example of a parameter set for a small local testnet.
+///
+/// It is [`MinimalConfig`] with shorter epochs, smaller committees and a lower churn limit
+/// quotient. The associated types determine the sizes of SSZ collections, so a new configuration
+/// has to provide all of them, but they can be taken from an existing preset. The parameters
+/// defined as functions default to their mainnet values, so only the ones that differ from
+/// mainnet need to be overridden.
+#[derive(
+    Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Debug, Deserialize, Serialize,
+)]
+pub struct CustomTestnetConfig;
+
+impl Config for CustomTestnetConfig {
+    type EpochsPerSlashingsVector = <MinimalConfig as Config>::EpochsPerSlashingsVector;
+    type EpochsPerHistoricalVector = <MinimalConfig as Config>::EpochsPerHistoricalVector;
+    type HistoricalRootsLimit = <MinimalConfig as Config>::HistoricalRootsLimit;
+    type MaxAttesterSlashings = <MinimalConfig as Config>::MaxAttesterSlashings;
+    type MaxAttestations = <MinimalConfig as Config>::MaxAttestations;
+    type MaxAttestationsPerEpoch = Prod<Self::MaxAttestations, Self::SlotsPerEpoch>;
+    type MaxDeposits = <MinimalConfig as Config>::MaxDeposits;
+    type MaxProposerSlashings = <MinimalConfig as Config>::MaxProposerSlashings;
+    type MaxValidatorsPerCommittee = <MinimalConfig as Config>::MaxValidatorsPerCommittee;
+    type MaxVoluntaryExits = <MinimalConfig as Config>::MaxVoluntaryExits;
+    type SecondsPerSlot = <MinimalConfig as Config>::SecondsPerSlot;
+    type SlotsPerEpoch = typenum::U4;
+    type SlotsPerEth1VotingPeriod = <MinimalConfig as Config>::SlotsPerEth1VotingPeriod;
+    type SlotsPerHistoricalRoot = <MinimalConfig as Config>::SlotsPerHistoricalRoot;
+    type ValidatorRegistryLimit = <MinimalConfig as Config>::ValidatorRegistryLimit;
+
+    fn churn_limit_quotient() -> u64 {
+        32
+    }
+    fn genesis_fork_version() -> Version {
+        [0, 0, 0, 2]
+    }
+    fn max_committees_per_slot() -> u64 {
+        MinimalConfig::max_committees_per_slot()
+    }
+    fn shuffle_round_count() -> u64 {
+        MinimalConfig::shuffle_round_count()
+    }
+    fn target_committee_size() -> u64 {
+        2
+    }
+}
@@ -8,4 +8,11 @@ pub enum Error {
     NumberExceedsCapacity,
     ArrayIsEmpty,
     NotAHash,
+    BlockSlotMismatch,
+    BlockParentMismatch,
+    ProposerSlashed,
+    CommitteeOutOfRange,
+    CommitteeLengthMismatch { expected: usize, got: usize },
+    PubKeyConversionError,
+    SignatureConversionError,
 }
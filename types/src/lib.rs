@@ -7,6 +7,7 @@ pub mod config;
 pub mod consts;
 pub mod helper_functions_types;
 pub mod primitives;
+pub mod runtime_config;
 pub mod types;
 
 pub use crate::beacon_state::{Error as BeaconStateError, *};
@@ -0,0 +1,167 @@
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{config::Config, primitives::Version};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to read configuration file: {0}")]
+    Io(#[from] io::Error),
+    #[error("failed to parse configuration file: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+/// The parameters of a [`Config`] that can be represented as values.
+///
+/// Parameters that are associated types of [`Config`] (like `SlotsPerEpoch`) determine the sizes
+/// of SSZ collections and cannot be loaded at runtime. Neither can the signature domains, which
+/// are encoded as bytes in configuration files but as integers in [`Config`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct RuntimeConfig {
+    pub base_reward_factor: u64,
+    pub churn_limit_quotient: u64,
+    pub effective_balance_increment: u64,
+    pub ejection_balance: u64,
+    pub genesis_epoch: u64,
+    #[serde(with = "fork_version")]
+    pub genesis_fork_version: Version,
+    pub genesis_slot: u64,
+    pub hysteresis_downward_multiplier: u64,
+    pub hysteresis_quotient: u64,
+    pub hysteresis_upward_multiplier: u64,
+    pub inactivity_penalty_quotient: u64,
+    pub max_committees_per_slot: u64,
+    pub max_effective_balance: u64,
+    pub min_attestation_inclusion_delay: u64,
+    pub min_deposit_amount: u64,
+    pub min_epochs_to_inactivity_penalty: u64,
+    pub min_genesis_active_validator_count: u64,
+    pub min_genesis_time: u64,
+    pub min_per_epoch_churn_limit: u64,
+    pub min_seed_lookahead: u64,
+    pub min_slashing_penalty_quotient: u64,
+    pub min_validator_withdrawability_delay: u64,
+    pub persistent_committee_period: u64,
+    pub proportional_slashing_multiplier: u64,
+    pub proposer_reward_quotient: u64,
+    pub shuffle_round_count: u64,
+    pub target_committee_size: u64,
+    pub whistleblower_reward_quotient: u64,
+}
+
+impl RuntimeConfig {
+    pub fn from_config<C: Config>() -> Self {
+        Self {
+            base_reward_factor: C::base_reward_factor(),
+            churn_limit_quotient: C::churn_limit_quotient(),
+            effective_balance_increment: C::effective_balance_increment(),
+            ejection_balance: C::ejection_balance(),
+            genesis_epoch: C::genesis_epoch(),
+            genesis_fork_version: C::genesis_fork_version(),
+            genesis_slot: C::genesis_slot(),
+            hysteresis_downward_multiplier: C::hysteresis_downward_multiplier(),
+            hysteresis_quotient: C::hysteresis_quotient(),
+            hysteresis_upward_multiplier: C::hysteresis_upward_multiplier(),
+            inactivity_penalty_quotient: C::inactivity_penalty_quotient(),
+            max_committees_per_slot: C::max_committees_per_slot(),
+            max_effective_balance: C::max_effective_balance(),
+            min_attestation_inclusion_delay: C::min_attestation_inclusion_delay(),
+            min_deposit_amount: C::min_deposit_amount(),
+            min_epochs_to_inactivity_penalty: C::min_epochs_to_inactivity_penalty(),
+            min_genesis_active_validator_count: C::min_genesis_active_validator_count(),
+            min_genesis_time: C::min_genesis_time(),
+            min_per_epoch_churn_limit: C::min_per_epoch_churn_limit(),
+            min_seed_lookahead: C::min_seed_lookahead(),
+            min_slashing_penalty_quotient: C::min_slashing_penalty_quotient(),
+            min_validator_withdrawability_delay: C::min_validator_withdrawability_delay(),
+            persistent_committee_period: C::persistent_committee_period(),
+            proportional_slashing_multiplier: C::proportional_slashing_multiplier(),
+            proposer_reward_quotient: C::proposer_reward_quotient(),
+            shuffle_round_count: C::shuffle_round_count(),
+            target_committee_size: C::target_committee_size(),
+            whistleblower_reward_quotient: C::whistleblower_reward_quotient(),
+        }
+    }
+
+    /// Loads parameters from a YAML file in the format used by the specification.
+    ///
+    /// Keys that do not correspond to fields of [`RuntimeConfig`] are ignored.
+    pub fn from_yaml(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let yaml = fs::read_to_string(path)?;
+        Ok(serde_yaml::from_str(&yaml)?)
+    }
+}
+
+// Fork versions are written as hexadecimal strings like `0x00000001` in configuration files.
+mod fork_version {
+    use core::fmt::Write as _;
+
+    use serde::{de::Error as _, Deserialize as _, Deserializer, Serializer};
+
+    use crate::primitives::Version;
+
+    pub fn serialize<S: Serializer>(version: &Version, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut string = String::from("0x");
+        for byte in version {
+            write!(string, "{:02x}", byte).expect("writing to a String should not fail");
+        }
+        serializer.serialize_str(&string)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Version, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        let digits = if string.starts_with("0x") {
+            &string[2..]
+        } else {
+            &string[..]
+        };
+        let mut version = Version::default();
+        if !digits.is_ascii() || digits.len() != 2 * version.len() {
+            return Err(D::Error::custom(format!(
+                "fork version {} is not 4 bytes in hexadecimal",
+                string,
+            )));
+        }
+        for (index, byte) in version.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[2 * index..2 * index + 2], 16)
+                .map_err(D::Error::custom)?;
+        }
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::MinimalConfig;
+
+    use super::*;
+
+    #[test]
+    fn minimal_preset_matches_minimal_config() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("presets/minimal.yaml");
+        let runtime_config = RuntimeConfig::from_yaml(path).expect("preset should be valid");
+        assert_eq!(
+            runtime_config,
+            RuntimeConfig::from_config::<MinimalConfig>()
+        );
+    }
+
+    #[test]
+    fn yaml_round_trip_preserves_fork_version_and_balance_parameters() {
+        let runtime_config = RuntimeConfig::from_config::<MinimalConfig>();
+        let yaml = serde_yaml::to_string(&runtime_config).expect("config should be serialized");
+
+        let decoded: RuntimeConfig =
+            serde_yaml::from_str(&yaml).expect("serialized config should be valid");
+
+        assert_eq!(decoded, runtime_config);
+        assert_eq!(decoded.genesis_fork_version, [0, 0, 0, 1]);
+        assert_eq!(decoded.hysteresis_downward_multiplier, 1);
+        assert_eq!(decoded.hysteresis_quotient, 4);
+        assert_eq!(decoded.hysteresis_upward_multiplier, 5);
+        assert_eq!(decoded.proportional_slashing_multiplier, 3);
+    }
+}
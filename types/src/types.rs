@@ -6,7 +6,7 @@ use ssz_derive::{Decode, Encode};
 use ssz_types::{BitList, FixedVector, VariableList};
 use tree_hash::TreeHash;
 use tree_hash_derive::{SignedRoot, TreeHash};
-use typenum::{Sum, U1};
+use typenum::{Sum, Unsigned as _, U1};
 
 use crate::config::*;
 use crate::consts;
@@ -42,14 +42,6 @@ pub struct AttestationData {
     pub target: Checkpoint,
 }
 
-#[derive(
-    Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot,
-)]
-pub struct AttestationDataAndCustodyBit {
-    pub data: AttestationData,
-    pub custody_bit: bool,
-}
-
 #[derive(
     Clone,
     Copy,
@@ -77,27 +69,12 @@ pub struct AttesterSlashing<C: Config> {
     pub attestation_2: IndexedAttestation<C>,
 }
 
-#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot)]
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize, Encode, Decode, TreeHash)]
 pub struct BeaconBlock<C: Config> {
     pub slot: Slot,
     pub parent_root: H256,
     pub state_root: H256,
     pub body: BeaconBlockBody<C>,
-    #[signed_root(skip_hashing)]
-    pub signature: Signature,
-}
-
-impl<C: Config> Default for BeaconBlock<C> {
-    fn default() -> Self {
-        #[allow(clippy::default_trait_access)]
-        Self {
-            slot: Default::default(),
-            parent_root: Default::default(),
-            state_root: Default::default(),
-            body: Default::default(),
-            signature: Signature::empty_signature(),
-        }
-    }
 }
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot)]
@@ -109,7 +86,7 @@ pub struct BeaconBlockBody<C: Config> {
     pub attester_slashings: VariableList<AttesterSlashing<C>, C::MaxAttesterSlashings>,
     pub attestations: VariableList<Attestation<C>, C::MaxAttestations>,
     pub deposits: VariableList<Deposit, C::MaxDeposits>,
-    pub voluntary_exits: VariableList<VoluntaryExit, C::MaxVoluntaryExits>,
+    pub voluntary_exits: VariableList<SignedVoluntaryExit, C::MaxVoluntaryExits>,
 }
 
 impl<C: Config> Default for BeaconBlockBody<C> {
@@ -145,29 +122,12 @@ impl<C: Config> Default for BeaconBlockBody<C> {
 //     }
 // }
 
-#[derive(
-    Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot,
-)]
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize, Encode, Decode, TreeHash)]
 pub struct BeaconBlockHeader {
     pub slot: Slot,
     pub parent_root: H256,
     pub state_root: H256,
     pub body_root: H256,
-    #[signed_root(skip_hashing)]
-    pub signature: Signature,
-}
-
-impl Default for BeaconBlockHeader {
-    fn default() -> Self {
-        #[allow(clippy::default_trait_access)]
-        Self {
-            slot: Default::default(),
-            parent_root: Default::default(),
-            state_root: Default::default(),
-            body_root: Default::default(),
-            signature: Signature::empty_signature(),
-        }
-    }
 }
 
 impl BeaconBlockHeader {
@@ -195,17 +155,6 @@ pub struct Checkpoint {
     pub root: H256,
 }
 
-#[derive(
-    Clone, PartialEq, Eq, Debug, Default, Hash, Deserialize, Serialize, Encode, Decode, TreeHash,
-)]
-pub struct Crosslink {
-    pub shard: u64,
-    pub parent_root: H256,
-    pub start_epoch: Epoch,
-    pub end_epoch: Epoch,
-    pub data_root: H256,
-}
-
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
 pub struct Deposit {
     pub proof: FixedVector<H256, Sum<consts::DepositContractTreeDepth, U1>>,
@@ -249,6 +198,30 @@ pub struct Fork {
     pub epoch: Epoch,
 }
 
+impl Fork {
+    /// Returns `true` if no fork has happened yet, i.e. both versions are the same.
+    pub fn is_genesis_fork(&self) -> bool {
+        self.previous_version == self.current_version
+    }
+
+    /// Returns the fork version in effect at `epoch`.
+    pub fn version_at_epoch(&self, epoch: Epoch) -> Version {
+        if epoch < self.epoch {
+            self.previous_version
+        } else {
+            self.current_version
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Default,
+)]
+pub struct ForkData {
+    pub current_version: Version,
+    pub genesis_validators_root: H256,
+}
+
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
 pub struct HistoricalBatch<C: Config> {
     pub block_roots: FixedVector<H256, C::SlotsPerHistoricalRoot>,
@@ -273,14 +246,17 @@ pub struct PendingAttestation<C: Config> {
     pub proposer_index: u64,
 }
 
-impl<C> Default for PendingAttestation<C>
+impl<C> PendingAttestation<C>
 where
     C: Config,
 {
-    fn default() -> Self {
+    /// Creates a `PendingAttestation` with room for a bit for every member of the largest
+    /// possible committee.
+    pub fn empty() -> Self {
         #[allow(clippy::default_trait_access)]
         Self {
-            aggregation_bits: BitList::with_capacity(2048).unwrap(),
+            aggregation_bits: BitList::with_capacity(C::MaxValidatorsPerCommittee::to_usize())
+                .expect("MaxValidatorsPerCommittee should be within the bounds of the BitList"),
             data: Default::default(),
             inclusion_delay: Default::default(),
             proposer_index: Default::default(),
@@ -288,11 +264,85 @@ where
     }
 }
 
+impl<C> Default for PendingAttestation<C>
+where
+    C: Config,
+{
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
 pub struct ProposerSlashing {
     pub proposer_index: u64,
-    pub header_1: BeaconBlockHeader,
-    pub header_2: BeaconBlockHeader,
+    pub signed_header_1: SignedBeaconBlockHeader,
+    pub signed_header_2: SignedBeaconBlockHeader,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
+pub struct SignedBeaconBlock<C: Config> {
+    pub message: BeaconBlock<C>,
+    pub signature: Signature,
+}
+
+impl<C: Config> Default for SignedBeaconBlock<C> {
+    fn default() -> Self {
+        #[allow(clippy::default_trait_access)]
+        Self {
+            message: Default::default(),
+            signature: Signature::empty_signature(),
+        }
+    }
+}
+
+impl<C: Config> SignedBeaconBlock<C> {
+    /// Returns the root of `message`, which is what blocks are identified by.
+    ///
+    /// The root is not cached. Callers that need it more than once should keep it around.
+    pub fn message_root(&self) -> H256 {
+        Hash256::from_slice(&self.message.tree_hash_root()[..])
+    }
+
+    pub fn slot(&self) -> Slot {
+        self.message.slot
+    }
+
+    pub fn parent_root(&self) -> H256 {
+        self.message.parent_root
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
+pub struct SignedBeaconBlockHeader {
+    pub message: BeaconBlockHeader,
+    pub signature: Signature,
+}
+
+impl Default for SignedBeaconBlockHeader {
+    fn default() -> Self {
+        #[allow(clippy::default_trait_access)]
+        Self {
+            message: Default::default(),
+            signature: Signature::empty_signature(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash)]
+pub struct SignedVoluntaryExit {
+    pub message: VoluntaryExit,
+    pub signature: Signature,
+}
+
+impl Default for SignedVoluntaryExit {
+    fn default() -> Self {
+        #[allow(clippy::default_trait_access)]
+        Self {
+            message: Default::default(),
+            signature: Signature::empty_signature(),
+        }
+    }
 }
 
 #[derive(
@@ -321,12 +371,210 @@ pub struct Validator {
     pub withdrawable_epoch: Epoch,
 }
 
-#[derive(
-    Clone, PartialEq, Eq, Debug, Deserialize, Serialize, Encode, Decode, TreeHash, SignedRoot,
-)]
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize, Encode, Decode, TreeHash)]
 pub struct VoluntaryExit {
     pub epoch: Epoch,
     pub validator_index: u64,
-    #[signed_root(skip_hashing)]
-    pub signature: Signature,
+}
+
+#[cfg(test)]
+mod tests {
+    use ssz::{Decode as _, Encode as _};
+    use typenum::U2;
+
+    use super::*;
+
+    #[test]
+    fn genesis_fork_has_the_same_version_at_all_epochs() {
+        let fork = Fork {
+            previous_version: [0, 0, 0, 1],
+            current_version: [0, 0, 0, 1],
+            epoch: 0,
+        };
+        assert!(fork.is_genesis_fork());
+        for epoch in &[0, 1, 100, u64::max_value()] {
+            assert_eq!(fork.version_at_epoch(*epoch), [0, 0, 0, 1]);
+        }
+    }
+
+    #[test]
+    fn fork_uses_previous_version_before_fork_epoch() {
+        let fork = Fork {
+            previous_version: [0, 0, 0, 1],
+            current_version: [0, 0, 0, 2],
+            epoch: 10,
+        };
+        assert!(!fork.is_genesis_fork());
+        assert_eq!(fork.version_at_epoch(0), [0, 0, 0, 1]);
+        assert_eq!(fork.version_at_epoch(9), [0, 0, 0, 1]);
+        assert_eq!(fork.version_at_epoch(10), [0, 0, 0, 2]);
+        assert_eq!(fork.version_at_epoch(11), [0, 0, 0, 2]);
+    }
+
+    fn attestation_with_bits(length: usize, set_bits: &[usize]) -> Attestation<MinimalConfig> {
+        let mut aggregation_bits = BitList::with_capacity(length).expect("length is within limit");
+        for bit in set_bits {
+            aggregation_bits
+                .set(*bit, true)
+                .expect("bit is within length");
+        }
+        Attestation {
+            aggregation_bits,
+            data: AttestationData::default(),
+            signature: AggregateSignature::new(),
+        }
+    }
+
+    #[test]
+    fn attestation_tree_hash_mixes_in_aggregation_bits_length() {
+        let short = attestation_with_bits(4, &[0, 2]);
+        let long = attestation_with_bits(8, &[0, 2]);
+        assert_ne!(short.tree_hash_root(), long.tree_hash_root());
+
+        let same_length = attestation_with_bits(8, &[0, 2]);
+        assert_eq!(long.tree_hash_root(), same_length.tree_hash_root());
+    }
+
+    fn signature() -> Signature {
+        Signature::new(&[1; 32], 0, &SecretKey::random())
+    }
+
+    #[test]
+    fn signed_beacon_block_accessors_read_message() {
+        let signed_block = SignedBeaconBlock::<MinimalConfig> {
+            message: BeaconBlock {
+                slot: 3,
+                parent_root: H256::repeat_byte(1),
+                ..BeaconBlock::default()
+            },
+            signature: signature(),
+        };
+
+        let message_root = signed_block.message_root();
+
+        assert_eq!(
+            message_root,
+            Hash256::from_slice(&signed_block.message.tree_hash_root()[..]),
+        );
+        assert_eq!(signed_block.message_root(), message_root);
+        assert_ne!(
+            message_root,
+            Hash256::from_slice(&signed_block.tree_hash_root()[..]),
+        );
+        assert_eq!(signed_block.slot(), 3);
+        assert_eq!(signed_block.parent_root(), H256::repeat_byte(1));
+    }
+
+    #[test]
+    fn signed_beacon_block_ssz_round_trip() {
+        let signed_block = SignedBeaconBlock::<MinimalConfig> {
+            message: BeaconBlock {
+                slot: 1,
+                parent_root: H256::repeat_byte(1),
+                body: BeaconBlockBody {
+                    randao_reveal: signature(),
+                    ..BeaconBlockBody::default()
+                },
+                ..BeaconBlock::default()
+            },
+            signature: signature(),
+        };
+        assert_eq!(
+            SignedBeaconBlock::from_ssz_bytes(&signed_block.as_ssz_bytes()),
+            Ok(signed_block),
+        );
+    }
+
+    #[test]
+    fn graffiti_survives_ssz_round_trip() {
+        let mut graffiti = [0; 32];
+        graffiti[..5].copy_from_slice(b"hello");
+        graffiti[31] = 0xff;
+        let body = BeaconBlockBody::<MinimalConfig> {
+            graffiti,
+            ..BeaconBlockBody::default()
+        };
+
+        let decoded = BeaconBlockBody::<MinimalConfig>::from_ssz_bytes(&body.as_ssz_bytes())
+            .expect("encoded body should be valid");
+        assert_eq!(decoded.graffiti, graffiti);
+        assert_ne!(
+            body.tree_hash_root(),
+            BeaconBlockBody::<MinimalConfig>::default().tree_hash_root()
+        );
+    }
+
+    #[test]
+    fn signed_beacon_block_header_ssz_round_trip() {
+        let signed_header = SignedBeaconBlockHeader {
+            message: BeaconBlockHeader {
+                slot: 1,
+                body_root: H256::repeat_byte(1),
+                ..BeaconBlockHeader::default()
+            },
+            signature: signature(),
+        };
+        assert_eq!(
+            SignedBeaconBlockHeader::from_ssz_bytes(&signed_header.as_ssz_bytes()),
+            Ok(signed_header),
+        );
+    }
+
+    #[test]
+    fn signed_voluntary_exit_ssz_round_trip() {
+        let signed_exit = SignedVoluntaryExit {
+            message: VoluntaryExit {
+                epoch: 1,
+                validator_index: 2,
+            },
+            signature: signature(),
+        };
+        assert_eq!(
+            SignedVoluntaryExit::from_ssz_bytes(&signed_exit.as_ssz_bytes()),
+            Ok(signed_exit),
+        );
+    }
+
+    #[test]
+    fn variable_list_of_fixed_size_items_rejects_too_many_items() {
+        let bytes = vec![1_u64, 2, 3].as_ssz_bytes();
+        assert!(VariableList::<u64, U2>::from_ssz_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn variable_list_of_variable_size_items_rejects_too_many_items() {
+        let bytes = vec![vec![1_u8], vec![2], vec![3]].as_ssz_bytes();
+        assert!(VariableList::<Vec<u8>, U2>::from_ssz_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn variable_list_accepts_items_up_to_maximum() {
+        let bytes = vec![1_u64, 2].as_ssz_bytes();
+        assert_eq!(
+            VariableList::<u64, U2>::from_ssz_bytes(&bytes),
+            Ok(VariableList::from(vec![1, 2])),
+        );
+    }
+
+    #[derive(PartialEq, Debug, Encode, Decode)]
+    struct ContainerWithOption {
+        before: u16,
+        optional: Option<Vec<u8>>,
+        after: Vec<u8>,
+    }
+
+    #[test]
+    fn variable_size_option_in_container_ssz_round_trip() {
+        for optional in vec![None, Some(vec![]), Some(vec![1, 2, 3])] {
+            let container = ContainerWithOption {
+                before: 0x0102,
+                optional,
+                after: vec![4, 5],
+            };
+            assert_eq!(
+                ContainerWithOption::from_ssz_bytes(&container.as_ssz_bytes()),
+                Ok(container),
+            );
+        }
+    }
 }